@@ -0,0 +1,232 @@
+//! The layouts of the bitfields decoded by the hand written modules.
+//!
+//! A layout is recorded while the prot2rust bitfield is declared, so the field widths
+//! and positions are written down once. The layout is rendered into a newtype with an
+//! accessor for each field, which the hand written modules decode through.
+
+use std::convert::TryInto;
+use std::fmt::{Debug, Write};
+
+/// A field of a layout.
+struct Field {
+    name: &'static str,
+    offset: u32,
+    bits: u32,
+    /// Single bit fields which are decoded as `bool`.
+    flag: bool,
+    doc: Option<&'static str>,
+}
+
+/// A method of the rendered newtype which selects between the alternatives of a
+/// structure using the fields of the layout.
+struct Selector {
+    doc: &'static str,
+    signature: &'static str,
+    body: &'static str,
+}
+
+/// The layout of a bitfield, recorded in the order the fields are added to the
+/// prot2rust `BitField`, starting at the least significant bit.
+pub struct Layout {
+    name: &'static str,
+    description: &'static str,
+    offset: u32,
+    fields: Vec<Field>,
+    selectors: Vec<Selector>,
+}
+
+impl Layout {
+    /// Creates an empty layout which is rendered as the newtype `name`.
+    pub fn new(name: &'static str, description: &'static str) -> Self {
+        Layout {
+            name,
+            description,
+            offset: 0,
+            fields: Vec::new(),
+            selectors: Vec::new(),
+        }
+    }
+
+    fn push<T>(&mut self, name: &'static str, bits: T, flag: bool, doc: Option<&'static str>) -> T
+    where
+        T: Copy + TryInto<u32>,
+        T::Error: Debug,
+    {
+        let width = bits.try_into().unwrap();
+        self.fields.push(Field {
+            name,
+            offset: self.offset,
+            bits: width,
+            flag,
+            doc,
+        });
+        self.offset += width;
+
+        bits
+    }
+
+    /// Records the next `bits` wide field, whose accessors are named after `name`.
+    /// Returns `bits` to be passed on to the prot2rust declaration.
+    pub fn field<T>(&mut self, name: &'static str, bits: T) -> T
+    where
+        T: Copy + TryInto<u32>,
+        T::Error: Debug,
+    {
+        self.push(name, bits, false, None)
+    }
+
+    /// Records the next single bit field, which is decoded as `bool`.
+    pub fn flag<T>(&mut self, name: &'static str, bits: T) -> T
+    where
+        T: Copy + TryInto<u32>,
+        T::Error: Debug,
+    {
+        self.push(name, bits, true, None)
+    }
+
+    /// Records the next field, which selects between alternatives as described by
+    /// `doc`.
+    pub fn selecting_field<T>(&mut self, name: &'static str, bits: T, doc: &'static str) -> T
+    where
+        T: Copy + TryInto<u32>,
+        T::Error: Debug,
+    {
+        self.push(name, bits, false, Some(doc))
+    }
+
+    /// Records the next single bit field, which selects between alternatives as
+    /// described by `doc`.
+    pub fn selecting_flag<T>(&mut self, name: &'static str, bits: T, doc: &'static str) -> T
+    where
+        T: Copy + TryInto<u32>,
+        T::Error: Debug,
+    {
+        self.push(name, bits, true, Some(doc))
+    }
+
+    /// Skips `bits` reserved bits.
+    pub fn reserved<T>(&mut self, bits: T) -> T
+    where
+        T: Copy + TryInto<u32>,
+        T::Error: Debug,
+    {
+        self.offset += bits.try_into().unwrap();
+        bits
+    }
+
+    /// Adds a method to the rendered newtype which selects an alternative from the
+    /// fields. `body` refers to the field accessors through `self`.
+    pub fn selector(&mut self, doc: &'static str, signature: &'static str, body: &'static str) {
+        self.selectors.push(Selector {
+            doc,
+            signature,
+            body,
+        });
+    }
+
+    /// Renders the newtype holding the bitfield with its accessors and selectors.
+    pub fn render(&self) -> String {
+        let ty = int_type(self.offset);
+        let value = widen(ty, "self.0");
+        let mut out = String::new();
+
+        writeln!(out, "/// {}", self.description).unwrap();
+        writeln!(out, "#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]").unwrap();
+        writeln!(out, "pub struct {}(pub {});\n", self.name, ty).unwrap();
+        writeln!(out, "impl {} {{", self.name).unwrap();
+
+        for field in &self.fields {
+            let get = format!("get({}, {}, {})", value, field.offset, field.bits);
+            let (field_ty, decoded) = if field.flag {
+                ("bool", format!("{} != 0", get))
+            } else {
+                let field_ty = int_type(field.bits);
+                let decoded = match field_ty {
+                    "u32" => get,
+                    _ => format!("{} as {}", get, field_ty),
+                };
+
+                let max = (1u64 << field.bits) - 1;
+                let name = field.name.to_uppercase();
+                writeln!(
+                    out,
+                    "    pub const {}_MAX: {} = {:#x};\n",
+                    name, field_ty, max
+                )
+                .unwrap();
+                (field_ty, decoded)
+            };
+
+            if let Some(doc) = field.doc {
+                writeln!(out, "    /// {}", doc).unwrap();
+            }
+            writeln!(out, "    pub fn {}(&self) -> {} {{", field.name, field_ty).unwrap();
+            writeln!(out, "        {}\n    }}\n", decoded).unwrap();
+
+            let set = format!(
+                "set({}, {}, {}, {})",
+                value,
+                field.offset,
+                field.bits,
+                widen(field_ty, "value")
+            );
+            let encoded = match ty {
+                "u32" => set,
+                _ => format!("{} as {}", set, ty),
+            };
+            writeln!(
+                out,
+                "    pub fn set_{}(&mut self, value: {}) {{",
+                field.name, field_ty
+            )
+            .unwrap();
+            writeln!(out, "        self.0 = {};\n    }}\n", encoded).unwrap();
+        }
+
+        for selector in &self.selectors {
+            for line in selector.doc.lines() {
+                writeln!(out, "{}", format!("    /// {}", line).trim_end()).unwrap();
+            }
+            writeln!(out, "    pub {} {{", selector.signature).unwrap();
+            for line in selector.body.trim_matches('\n').lines() {
+                writeln!(out, "{}", format!("        {}", line).trim_end()).unwrap();
+            }
+            writeln!(out, "    }}\n").unwrap();
+        }
+
+        // drop the blank line after the last item
+        out.truncate(out.trim_end().len());
+        out.push_str("\n}\n");
+        out
+    }
+}
+
+/// Converts `expr` of type `ty` to `u32`.
+fn widen(ty: &str, expr: &str) -> String {
+    match ty {
+        "u32" => expr.to_string(),
+        _ => format!("u32::from({})", expr),
+    }
+}
+
+/// The smallest unsigned integer type holding `bits` bits.
+fn int_type(bits: u32) -> &'static str {
+    match bits {
+        0..=8 => "u8",
+        9..=16 => "u16",
+        _ => "u32",
+    }
+}
+
+/// Renders the layouts after the hand written `prelude`, which provides the `get` and
+/// `set` functions used by the accessors.
+pub fn render(prelude: &str, layouts: &[Layout]) -> String {
+    let mut out = String::from(prelude);
+
+    for layout in layouts {
+        out.push('\n');
+        out.push_str(&layout.render());
+    }
+
+    out
+}
@@ -0,0 +1,145 @@
+//! Generator for the IEEE 802.15.4 frame definitions.
+//!
+//! The generator can be invoked from a build script to generate the definitions into
+//! `OUT_DIR`:
+//!
+//! ```no_run
+//! use std::env;
+//!
+//! use rust_ieee802154_gen::{generate, Config};
+//!
+//! let out_dir = env::var("OUT_DIR").unwrap();
+//! generate(&Config::new(out_dir).only("beacon").only("security")).unwrap();
+//! ```
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+
+use output::Output;
+
+pub use targets::{Render, Target, TARGETS};
+
+mod layout;
+mod output;
+pub mod render_beacon;
+pub mod render_crate;
+pub mod render_frame;
+pub mod render_ie;
+pub mod render_mac_command;
+pub mod render_security;
+mod targets;
+
+/// Configuration of a single generator run.
+pub struct Config {
+    out_dir: PathBuf,
+    only: Vec<String>,
+    dry_run: bool,
+    manifest: bool,
+}
+
+impl Config {
+    /// Creates a configuration which generates all targets into `out_dir`.
+    pub fn new<P: Into<PathBuf>>(out_dir: P) -> Self {
+        Config {
+            out_dir: out_dir.into(),
+            only: Vec::new(),
+            dry_run: false,
+            manifest: true,
+        }
+    }
+
+    /// Restricts the generated targets to the group or target `name`. May be called
+    /// multiple times to select several of them.
+    pub fn only(mut self, name: &str) -> Self {
+        self.only.push(name.to_string());
+        self
+    }
+
+    /// Renders the targets without writing any files. `generate` still returns the
+    /// paths of the files.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Specifies if the `Cargo.toml` of the generated crate is written. It can be
+    /// disabled when the sources are included into an existing crate.
+    pub fn manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Returns the targets selected by this configuration, including the targets they
+    /// require.
+    pub fn targets(&self) -> Result<Vec<&'static Target>> {
+        if self.only.is_empty() {
+            return Ok(TARGETS.iter().collect());
+        }
+
+        if let Some(name) = self
+            .only
+            .iter()
+            .find(|name| !TARGETS.iter().any(|t| t.matches(name)))
+        {
+            bail!("unknown group or target '{}'", name);
+        }
+
+        let mut selected: Vec<&Target> = TARGETS
+            .iter()
+            .filter(|t| self.only.iter().any(|name| t.matches(name)))
+            .collect();
+
+        // add the required targets, including those required by the added targets
+        let mut i = 0;
+        while i < selected.len() {
+            let target = selected[i];
+            for name in target.requires {
+                let required = TARGETS.iter().find(|t| t.name == *name).ok_or_else(|| {
+                    anyhow!("'{}' requires unknown target '{}'", target.name, name)
+                })?;
+                if !selected.iter().any(|t| t.name == required.name) {
+                    selected.push(required);
+                }
+            }
+            i += 1;
+        }
+
+        Ok(TARGETS
+            .iter()
+            .filter(|t| selected.iter().any(|s| s.name == t.name))
+            .collect())
+    }
+}
+
+/// Generates the crate described by `config`. Returns the paths of the generated
+/// files, which are not written in dry run mode.
+pub fn generate(config: &Config) -> Result<Vec<PathBuf>> {
+    let targets = config.targets()?;
+
+    let output = Output::new(config.out_dir.clone(), config.dry_run);
+    let mut paths = Vec::new();
+    for target in &targets {
+        let path = format!("{}/{}", render_crate::SOURCE_DIR, target.path);
+        paths.push(match target.render {
+            Render::Generated(render) => output.write(render()?, &path)?,
+            Render::Source(render) => {
+                output.write_text(&render_crate::render_source(render()), &path)?
+            }
+            Render::Rendered(render) => {
+                output.write_text(&render_crate::render_source(&render()?), &path)?
+            }
+        });
+    }
+
+    for (path, contents) in render_crate::render_modules(&targets) {
+        let path = format!("{}/{}", render_crate::SOURCE_DIR, path);
+        paths.push(output.write_text(&contents, &path)?);
+    }
+
+    if config.manifest {
+        paths.push(output.write_text(&render_crate::render_manifest(), "Cargo.toml")?);
+    }
+
+    Ok(paths)
+}
@@ -0,0 +1,60 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+
+use prot2rust::file::GenFile;
+
+/// The directory the generated crate is written into.
+pub struct Output {
+    root: PathBuf,
+    dry_run: bool,
+}
+
+impl Output {
+    pub fn new<P: Into<PathBuf>>(root: P, dry_run: bool) -> Self {
+        Output {
+            root: root.into(),
+            dry_run,
+        }
+    }
+
+    /// Writes `genfile` to `path`, relative to the output directory. Missing parent
+    /// directories are created. Returns the destination, which is not written in dry
+    /// run mode.
+    pub fn write(&self, genfile: GenFile, path: &str) -> Result<PathBuf> {
+        let path = self.root.join(path);
+
+        if self.dry_run {
+            return Ok(path);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let name = path
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid output path {}", path.display()))?;
+
+        genfile.write_file(name)?;
+        Ok(path)
+    }
+
+    /// Writes the `contents` of a hand assembled file, such as the crate manifest.
+    pub fn write_text(&self, contents: &str, path: &str) -> Result<PathBuf> {
+        let path = self.root.join(path);
+
+        if self.dry_run {
+            return Ok(path);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&path, contents)
+            .map_err(|e| anyhow!("failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
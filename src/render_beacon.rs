@@ -8,7 +8,7 @@ use prot2rust::{
     },
 };
 
-pub fn render_superframe() -> Result<()> {
+pub fn render_superframe() -> Result<GenFile> {
     let mut genfile = GenFile::new();
 
     let bitfield = bitfield::BitField::new("Superframe", "Superframe specification field.")
@@ -49,10 +49,10 @@ pub fn render_superframe() -> Result<()> {
 
     genfile.add_bitfield(&bitfield)?;
 
-    genfile.write_file("out/beacon/superframe.rs")
+    Ok(genfile)
 }
 
-pub fn render_gts_info() -> Result<()> {
+pub fn render_gts_info() -> Result<GenFile> {
     let mut genfile = GenFile::new();
 
     genfile.add_struct_imports()?;
@@ -75,10 +75,10 @@ pub fn render_gts_info() -> Result<()> {
 
     genfile.add_struct_with_alts(&structure, &alternatives)?;
 
-    genfile.write_file("out/beacon/gts_info.rs")
+    Ok(genfile)
 }
 
-pub fn render_gts_specification() -> Result<()> {
+pub fn render_gts_specification() -> Result<GenFile> {
     let mut genfile = GenFile::new();
 
     let bitfield = bitfield::BitField::new(
@@ -112,10 +112,10 @@ pub fn render_gts_specification() -> Result<()> {
 
     genfile.add_bitfield(&bitfield)?;
 
-    genfile.write_file("out/beacon/gts_specification.rs")
+    Ok(genfile)
 }
 
-pub fn render_gts_direction() -> Result<()> {
+pub fn render_gts_direction() -> Result<GenFile> {
     let mut genfile = GenFile::new();
 
     let bitfield =
@@ -130,10 +130,10 @@ pub fn render_gts_direction() -> Result<()> {
 
     genfile.add_bitfield(&bitfield)?;
 
-    genfile.write_file("out/beacon/gts_directions.rs")
+    Ok(genfile)
 }
 
-pub fn render_gts_descriptor_config() -> Result<()> {
+pub fn render_gts_descriptor_config() -> Result<GenFile> {
     let mut genfile = GenFile::new();
 
     let bitfield =
@@ -143,10 +143,10 @@ pub fn render_gts_descriptor_config() -> Result<()> {
 
     genfile.add_bitfield(&bitfield)?;
 
-    genfile.write_file("out/beacon/gts_descriptor_config.rs")
+    Ok(genfile)
 }
 
-pub fn render_gts_descriptor() -> Result<()> {
+pub fn render_gts_descriptor() -> Result<GenFile> {
     let mut genfile = GenFile::new();
 
     genfile.add_struct_imports()?;
@@ -157,10 +157,10 @@ pub fn render_gts_descriptor() -> Result<()> {
 
     genfile.add_struct(&structure)?;
 
-    genfile.write_file("out/beacon/gts_descriptor.rs")
+    Ok(genfile)
 }
 
-pub fn render_pending_address_spec() -> Result<()> {
+pub fn render_pending_address_spec() -> Result<GenFile> {
     let mut genfile = GenFile::new();
 
     let bitfield = bitfield::BitField::new("Pending_address_specification", "")
@@ -181,15 +181,13 @@ pub fn render_pending_address_spec() -> Result<()> {
 
     genfile.add_bitfield(&bitfield)?;
 
-    genfile.write_file("out/beacon/pending_address_specifications.rs")
+    Ok(genfile)
 }
 
-pub fn render() -> Result<()> {
-    render_superframe()?;
-    render_gts_specification()?;
-    render_gts_direction()?;
-    render_gts_descriptor_config()?;
-    render_gts_descriptor()?;
-    render_gts_info()?;
-    render_pending_address_spec()
+pub fn render_beacon_payload() -> &'static str {
+    include_str!("../templates/beacon/payload.rs")
+}
+
+pub fn render_enhanced_beacon() -> &'static str {
+    include_str!("../templates/beacon/enhanced.rs")
 }
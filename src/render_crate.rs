@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::targets::Target;
+
+/// The name of the generated crate.
+pub const CRATE_NAME: &str = "ieee802154";
+
+/// The oldest Rust version the generated crate builds with. It is stated in the
+/// documentation of the crate and as `rust-version` in its manifest, which is only
+/// understood from cargo 1.56 on; older toolchains warn about an unused manifest key.
+pub const RUST_VERSION: &str = "1.42";
+
+/// The directory within the generated crate that contains the sources.
+pub const SOURCE_DIR: &str = "src";
+
+const HEADER: &str = "// This file is generated by rust-ieee802154-gen, do not edit.\n";
+
+pub fn render_manifest() -> String {
+    format!(
+        "[package]
+name = \"{}\"
+version = \"0.1.0\"
+edition = \"2018\"
+# only understood from cargo 1.56 on, older toolchains warn about an unused key
+rust-version = \"{}\"
+description = \"IEEE 802.15.4 frame definitions generated by rust-ieee802154-gen.\"
+
+[dependencies]
+",
+        CRATE_NAME, RUST_VERSION
+    )
+}
+
+/// Renders `lib.rs` and a `mod.rs` for every subdirectory, such that each of the
+/// `targets` is part of the module tree. The returned paths are relative to the
+/// source directory.
+pub fn render_modules(targets: &[&Target]) -> Vec<(String, String)> {
+    let mut tree: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    tree.entry("").or_default();
+
+    for target in targets {
+        let module = target.path.trim_end_matches(".rs");
+        match module.rsplit_once('/') {
+            Some((dir, name)) => {
+                tree.entry(dir).or_default().push(name);
+                tree.entry("").or_default().push(dir);
+            }
+            None => tree.entry("").or_default().push(module),
+        }
+    }
+
+    tree.into_iter()
+        .map(|(dir, mut modules)| {
+            modules.sort_unstable();
+            modules.dedup();
+
+            if dir.is_empty() {
+                ("lib.rs".to_string(), render_lib(&modules))
+            } else {
+                (format!("{}/mod.rs", dir), render_mod(&modules))
+            }
+        })
+        .collect()
+}
+
+/// Prepends the generated file header to the hand written `source` of a target.
+pub fn render_source(source: &str) -> String {
+    format!("{}\n{}", HEADER, source)
+}
+
+fn render_lib(modules: &[&str]) -> String {
+    let mut lib = String::from(HEADER);
+
+    writeln!(
+        lib,
+        "\n//! IEEE 802.15.4 frame definitions.\n//!\n//! Requires Rust {} or later.\n\n#![no_std]\n",
+        RUST_VERSION
+    )
+    .unwrap();
+    for module in modules {
+        writeln!(lib, "pub mod {};", module).unwrap();
+    }
+
+    lib
+}
+
+fn render_mod(modules: &[&str]) -> String {
+    let mut file = String::from(HEADER);
+
+    file.push('\n');
+    for module in modules {
+        writeln!(file, "pub mod {};", module).unwrap();
+    }
+
+    file.push('\n');
+    for module in modules {
+        writeln!(file, "pub use self::{}::*;", module).unwrap();
+    }
+
+    file
+}
@@ -0,0 +1,356 @@
+use anyhow::Result;
+
+use prot2rust::generate::structure::{
+    AlternativeOptions, Alternatives, SimpleStructure, Structure,
+};
+use prot2rust::{file::GenFile, generate::bitfield};
+
+use crate::layout::{self, Layout};
+use crate::{render_ie, render_mac_command, render_security};
+
+pub fn render_mac() -> Result<GenFile> {
+    let mut genfile = GenFile::new();
+    mac(&mut genfile, &mut frame_control(&mut GenFile::new())?)?;
+
+    Ok(genfile)
+}
+
+/// Declares the MHR and its alternatives, adding the selection of the alternatives to
+/// the layout of the frame control field.
+fn mac(genfile: &mut GenFile, frame_control: &mut Layout) -> Result<()> {
+    genfile.add_struct_imports()?;
+
+    let addr_none = Structure::new("addr_none");
+    let addr_short = SimpleStructure::new("addr_short", "address", 2);
+    let addr_extended = SimpleStructure::new("addr_extended", "address", 8);
+
+    genfile.add_struct(&addr_none)?;
+    genfile.add_struct_simple(&addr_short)?;
+    genfile.add_struct_simple(&addr_extended)?;
+
+    let address = AlternativeOptions::new("address", &addr_none)
+        .insert_type(&addr_short)
+        .insert_type(&addr_extended);
+
+    let pan_none = Structure::new("pan_none");
+    let pan_short = SimpleStructure::new("pan_short", "pan", 2);
+
+    genfile.add_struct(&pan_none)?;
+    genfile.add_struct_simple(&pan_short)?;
+
+    let panid = AlternativeOptions::new("panid", &pan_none).insert_type(&pan_short);
+    frame_control.selector(
+        "Determines which of the destination and source PAN identifiers are present in the
+MHR, selecting the `panid` alternatives of `dest_pan` and `source_pan`. Returns `None`
+for the reserved frame version.
+
+Frames of versions 2003 and 2006 include a PAN identifier with each present address,
+unless PAN ID compression elides the source PAN identifier. Frames of the current
+version follow the PAN ID compression table (Table 7-2) of IEEE 802.15.4-2015.",
+        "fn pan_ids_present(&self) -> Option<(bool, bool)>",
+        "
+let dst_mode = self.dst_addr_mode();
+let src_mode = self.src_addr_mode();
+let (dst, src) = (dst_mode != 0b00, src_mode != 0b00);
+let compression = self.pan_id_compression();
+
+match self.frame_version() {
+    0b00 | 0b01 => Some((dst, src && !compression)),
+    0b10 => Some(match (dst, src) {
+        (false, false) => (compression, false),
+        (true, false) => (!compression, false),
+        (false, true) => (false, !compression),
+        // both addresses are extended
+        (true, true) if dst_mode == 0b11 && src_mode == 0b11 => (!compression, false),
+        (true, true) => (true, !compression),
+    }),
+    _ => None,
+}",
+    );
+
+    let seq_none = Structure::new("seq_none");
+    let seq_present = SimpleStructure::new("seq_present", "sequence_number", 1);
+
+    genfile.add_struct(&seq_none)?;
+    genfile.add_struct_simple(&seq_present)?;
+
+    let sequence_number =
+        AlternativeOptions::new("sequence_number", &seq_none).insert_type(&seq_present);
+    frame_control.selector(
+        "Determines if the sequence number is present in the MHR, selecting the
+`sequence_number` alternative. Sequence number suppression is only defined for frames
+of the current version, the bit is reserved in earlier versions.",
+        "fn sequence_number_present(&self) -> bool",
+        "self.frame_version() < 0b10 || !self.seq_nr_suppressed()",
+    );
+
+    let alternatives = Alternatives::new()
+        .insert(&address)
+        .insert(&panid)
+        .insert(&sequence_number);
+    genfile.add_alternatives(&alternatives)?;
+
+    // the alternatives are selected by the frame control field, see `frame_control`
+    let structure = Structure::new("mhr")
+        .add_bitfield("frame_control", "frame_control", 2)
+        .add_alt_field("sequence_number", &sequence_number)
+        .add_alt_field("dest_pan", &panid)
+        .add_alt_field("dest_address", &address)
+        .add_alt_field("source_pan", &panid)
+        .add_alt_field("source_address", &address);
+
+    genfile.add_struct_with_alts(&structure, &alternatives)?;
+
+    Ok(())
+}
+
+pub fn render_frame_control() -> Result<GenFile> {
+    let mut genfile = GenFile::new();
+    frame_control(&mut genfile)?;
+
+    Ok(genfile)
+}
+
+/// Declares the frame control field, returning its layout.
+fn frame_control(genfile: &mut GenFile) -> Result<Layout> {
+    let mut layout = Layout::new(
+        "FrameControl",
+        "The frame control field, see `Frame_control` for the description of the fields.",
+    );
+
+    let bitfield = bitfield::BitField::new(
+        "Frame_control",
+        "This field contains information about the frame type, addressing and control flags.",
+    )
+    .add_bit_field(
+        "Frame_type",
+        "This field contains information about the frame type, addressing and control flags.",
+        layout.field("frame_type", 3),
+        |v| {
+            v.add_enum_value("Beacon", 0b000)
+                .add_enum_value("Data", 0b001)
+                .add_enum_value("Acknowledgement", 0b010)
+                .add_enum_value("MAC_command", 0b011)
+                .add_enum_value("Reserved", 0b100)
+                .add_enum_value("Multipurpose", 0b101)
+                .add_enum_value("Fragment", 0b110)
+                .add_enum_value("Extended", 0b111)
+        },
+    )
+    .add_bit_field(
+        "Security_enabled",
+        "Specifies if the frame is encrypted using the key stored in the PIB.",
+        layout.flag("security_enabled", 1),
+        |v| {
+            v.add_enum_value("Unencrypted", 0)
+                .add_enum_value("Encrypted", 1)
+        },
+    )
+    .add_bit_field(
+        "Frame_pending",
+        "Specifies if the sender has additional data to send to the recipient.",
+        layout.flag("frame_pending", 1),
+        |v| {
+            v.add_enum_value("No_frame_pending", 0)
+                .add_enum_value("Frame_pending", 1)
+        },
+    )
+    .add_bit_field(
+        "Ack_request",
+        "Specifies whether an acknowledgement is required from the recipient device.",
+        layout.flag("ack_request", 1),
+        |v| {
+            v.add_enum_value("Ack_not_requested", 0)
+                .add_enum_value("Ack_requested", 1)
+        },
+    )
+    .add_bit_field(
+        "PAN_Compression",
+        "Specifies whether the MAC frame is to be sent within the same PAN.",
+        layout.selecting_flag(
+            "pan_id_compression",
+            1,
+            "Selects the `panid` alternatives, see `pan_ids_present`.",
+        ),
+        |v| {
+            v.add_enum_value("Uncompressed", 0)
+                .add_enum_value("Compressed", 1)
+        },
+    )
+    .add_reserved(layout.reserved(1))
+    .add_bit_field(
+        "Seq_nr_suppression",
+        "Specifies if the sequence number should be suppressed.",
+        layout.selecting_flag(
+            "seq_nr_suppressed",
+            1,
+            "Selects the `sequence_number` alternative, see `sequence_number_present`.",
+        ),
+        |v| {
+            v.add_enum_value("included", 0)
+                .add_enum_value("suppressed", 1)
+        },
+    )
+    .add_bit_field(
+        "IE_Present",
+        "Specified if Information Elements (IEs) are contained in the frame.",
+        layout.flag("ie_present", 1),
+        |v| v.add_enum_value("none", 0).add_enum_value("present", 1),
+    )
+    .add_bit_field(
+        "Dest_addr_mode",
+        "Specifies the type of the destination address.",
+        layout.selecting_field(
+            "dst_addr_mode",
+            2,
+            "Selects the `address` alternative of `dest_address`.",
+        ),
+        |v| {
+            v.add_enum_value("Not_present", 0)
+                .add_enum_value("Address_16bit", 0b10)
+                .add_enum_value("Address_64bit_extended", 0b11)
+        },
+    )
+    .add_bit_field(
+        "Frame_version",
+        "Specifies the version of the frame",
+        layout.field("frame_version", 2),
+        |v| {
+            v.add_enum_value("version_2003", 0b00)
+                .add_enum_value("version_2006", 0b01)
+                .add_enum_value("current", 0b10)
+        },
+    )
+    .add_bit_field(
+        "Source_addr_mode",
+        "Specifies the type of the source address.",
+        layout.selecting_field(
+            "src_addr_mode",
+            2,
+            "Selects the `address` alternative of `source_address`.",
+        ),
+        |v| {
+            v.add_enum_value("Not_present", 0)
+                .add_enum_value("Address_16bit", 0b10)
+                .add_enum_value("Address_64bit_extended", 0b11)
+        },
+    );
+
+    genfile.add_bitfield(&bitfield)?;
+
+    Ok(layout)
+}
+
+/// Renders the layouts of the bitfields decoded by the hand written modules, together
+/// with the selection of the alternatives which depend on them.
+pub fn render_layout() -> Result<String> {
+    let mut genfile = GenFile::new();
+
+    let mut frame_control = frame_control(&mut genfile)?;
+    mac(&mut genfile, &mut frame_control)?;
+
+    let mut layouts = vec![frame_control, ie_control(&mut genfile)?];
+    layouts.extend(render_ie::layouts()?);
+    layouts.extend(render_mac_command::layouts()?);
+    layouts.extend(render_security::layouts()?);
+
+    Ok(layout::render(
+        include_str!("../templates/layout.rs"),
+        &layouts,
+    ))
+}
+
+pub fn render_ie_control() -> Result<GenFile> {
+    let mut genfile = GenFile::new();
+    ie_control(&mut genfile)?;
+
+    Ok(genfile)
+}
+
+/// Declares the descriptor of header IEs, returning its layout.
+fn ie_control(genfile: &mut GenFile) -> Result<Layout> {
+    let mut layout = Layout::new(
+        "HeaderIeDescriptor",
+        "The descriptor of a header IE, see `IE Control` for the description of the fields.",
+    );
+
+    let bitfield = bitfield::BitField::new("IE Control", "Specifies the type of an IE header.")
+        .add_bit_field(
+            "Length",
+            "Specifies the length of the IE header contents.",
+            layout.field("length", 7),
+            |v| v,
+        )
+        .add_bit_field(
+            "Element_id",
+            "Specifies the type of the IE header.",
+            layout.field("element_id", 8),
+            |v| {
+                v.add_enum_value_desc("vendor_specific", "Vendor Specific Header IE", 0b00)
+                    .add_enum_value_desc("CSL_IE", "CSL IE", 0x1a)
+                    .add_enum_value_desc("RIT_IE", "RIT IE", 0x1b)
+                    .add_enum_value_desc("DSME_PAN", "DSME PAN descriptor IE", 0x1c)
+                    .add_enum_value_desc("Rendezvous Time IE", "Rendezvous Time IE", 0x1d)
+                    .add_enum_value_desc("Time_Correction_IE", "Time Correction IE", 0x1e)
+                    .add_enum_value_desc("Ext_DSME_PAN", "Extended DSME PAN descriptor IE", 0x21)
+                    .add_enum_value_desc(
+                        "Frag_seq_context",
+                        "Fragment Sequence Context Description (FSCD) IE",
+                        0x22,
+                    )
+                    .add_enum_value_desc(
+                        "Simpl_Superframe",
+                        "Simplified Superframe Specification IE",
+                        0x23,
+                    )
+                    .add_enum_value_desc("Simpl_GTS", "Simplified GTS Specification IE", 0x24)
+                    .add_enum_value_desc("LECIM_Capabilities", "LECIM Capabilities IE", 0x25)
+                    .add_enum_value_desc("TRLE_Descr", "TRLE Descriptor IE", 0x26)
+                    .add_enum_value_desc("RCC", "RCC Capabilities IE", 0x27)
+                    .add_enum_value_desc("RCCN", "RCCN Descriptor IE", 0x28)
+                    .add_enum_value_desc("Global_Time", "Global Time IE", 0x29)
+                    .add_enum_value_desc("External_ANA", "Assigned to external organization", 0x2a)
+                    .add_enum_value_desc("DA", "DA IE", 0x2b)
+                    .add_enum_value_desc("Header_termination_1", "Header Termination 1 IE", 0x7e)
+                    .add_enum_value_desc("Header_termination_2", "Header Termination 2 IE", 0x7f)
+            },
+        )
+        .add_bit_field(
+            "Type",
+            "Specifies the type of the IE header.",
+            layout.flag("payload_ie", 1),
+            |v| v.add_enum_value("default", 0),
+        );
+
+    genfile.add_bitfield(&bitfield)?;
+
+    Ok(layout)
+}
+
+pub fn render_parse_error() -> &'static str {
+    include_str!("../templates/error.rs")
+}
+
+pub fn render_header() -> &'static str {
+    include_str!("../templates/header.rs")
+}
+
+pub fn render_data_frame() -> &'static str {
+    include_str!("../templates/data_frame.rs")
+}
+
+pub fn render_ack_frame() -> &'static str {
+    include_str!("../templates/ack_frame.rs")
+}
+
+pub fn render_fcs() -> &'static str {
+    include_str!("../templates/fcs.rs")
+}
+
+pub fn render_frame() -> &'static str {
+    include_str!("../templates/frame.rs")
+}
+
+pub fn render_frame_builder() -> &'static str {
+    include_str!("../templates/builder.rs")
+}
@@ -0,0 +1,211 @@
+use anyhow::Result;
+
+use prot2rust::{file::GenFile, generate::bitfield};
+
+use crate::layout::Layout;
+
+pub fn render_header_ies() -> &'static str {
+    include_str!("../templates/ie/header.rs")
+}
+
+pub fn render_payload_ie_control() -> Result<GenFile> {
+    let mut genfile = GenFile::new();
+    payload_ie_control(&mut genfile)?;
+
+    Ok(genfile)
+}
+
+/// Declares the descriptor of payload IEs, returning its layout.
+fn payload_ie_control(genfile: &mut GenFile) -> Result<Layout> {
+    let mut layout = Layout::new(
+        "PayloadIeDescriptor",
+        "The descriptor of a payload IE, see `Payload_IE_control` for the description of the \
+         fields.",
+    );
+
+    let bitfield =
+        bitfield::BitField::new("Payload_IE_control", "Specifies the type of a payload IE.")
+            .add_bit_field(
+                "Length",
+                "Specifies the length of the payload IE contents.",
+                layout.field("length", 11),
+                |v| v.numeric(),
+            )
+            .add_bit_field(
+                "Group_id",
+                "Specifies the group of the payload IE.",
+                layout.field("group_id", 4),
+                |v| {
+                    v.add_enum_value_desc("ESDU", "Encapsulated Service Data Unit IE", 0x0)
+                        .add_enum_value_desc("MLME", "MLME IE containing nested IEs", 0x1)
+                        .add_enum_value_desc("vendor_specific", "Vendor Specific Payload IE", 0x2)
+                        .add_enum_value_desc("IETF", "IETF IE", 0x5)
+                        .add_enum_value_desc("Payload_termination", "Payload Termination IE", 0xf)
+                },
+            )
+            .add_bit_field(
+                "Type",
+                "Specifies the type of the IE.",
+                layout.flag("payload_ie", 1),
+                |v| v.add_enum_value("payload", 1),
+            );
+
+    genfile.add_bitfield(&bitfield)?;
+
+    Ok(layout)
+}
+
+pub fn render_nested_ie_control() -> Result<GenFile> {
+    let mut genfile = GenFile::new();
+    nested_ie_control(&mut genfile)?;
+
+    Ok(genfile)
+}
+
+/// Declares the descriptors of short and long nested IEs, returning their layouts.
+fn nested_ie_control(genfile: &mut GenFile) -> Result<(Layout, Layout)> {
+    let mut short_layout = Layout::new(
+        "NestedIeShortDescriptor",
+        "The descriptor of a short nested IE, see `Nested_IE_short_control` for the \
+         description of the fields.",
+    );
+    let mut long_layout = Layout::new(
+        "NestedIeLongDescriptor",
+        "The descriptor of a long nested IE, see `Nested_IE_long_control` for the \
+         description of the fields.",
+    );
+
+    let short = bitfield::BitField::new(
+        "Nested_IE_short_control",
+        "Specifies the type of a short nested IE within an MLME IE.",
+    )
+    .add_bit_field(
+        "Length",
+        "Specifies the length of the nested IE contents.",
+        short_layout.field("length", 8),
+        |v| v.numeric(),
+    )
+    .add_bit_field(
+        "Sub_id",
+        "Specifies the nested IE.",
+        short_layout.field("sub_id", 7),
+        |v| v.numeric(),
+    )
+    .add_bit_field(
+        "Type",
+        "Specifies the type of the nested IE.",
+        short_layout.flag("long", 1),
+        |v| v.add_enum_value("short", 0),
+    );
+
+    let long = bitfield::BitField::new(
+        "Nested_IE_long_control",
+        "Specifies the type of a long nested IE within an MLME IE.",
+    )
+    .add_bit_field(
+        "Length",
+        "Specifies the length of the nested IE contents.",
+        long_layout.field("length", 11),
+        |v| v.numeric(),
+    )
+    .add_bit_field(
+        "Sub_id",
+        "Specifies the nested IE.",
+        long_layout.field("sub_id", 4),
+        |v| v.numeric(),
+    )
+    .add_bit_field(
+        "Type",
+        "Specifies the type of the nested IE.",
+        long_layout.flag("long", 1),
+        |v| v.add_enum_value("long", 1),
+    );
+
+    genfile.add_bitfield(&short)?;
+    genfile.add_bitfield(&long)?;
+
+    Ok((short_layout, long_layout))
+}
+
+pub fn render_payload_ies() -> &'static str {
+    include_str!("../templates/ie/payload.rs")
+}
+
+pub fn render_header_ie_content() -> &'static str {
+    include_str!("../templates/ie/content.rs")
+}
+
+pub fn render_tsch_ies() -> &'static str {
+    include_str!("../templates/ie/tsch.rs")
+}
+
+pub fn render_frame_ies() -> &'static str {
+    include_str!("../templates/ie/list.rs")
+}
+
+pub fn render_eb_filter_descriptor() -> Result<GenFile> {
+    let mut genfile = GenFile::new();
+    eb_filter_descriptor(&mut genfile)?;
+
+    Ok(genfile)
+}
+
+/// Declares the descriptor of the EB Filter IE, returning its layout.
+fn eb_filter_descriptor(genfile: &mut GenFile) -> Result<Layout> {
+    let mut layout = Layout::new(
+        "EbFilterDescriptor",
+        "The descriptor of the EB Filter IE, see `EB_filter_descriptor` for the description \
+         of the fields.",
+    );
+
+    let bitfield = bitfield::BitField::new(
+        "EB_filter_descriptor",
+        "Specifies the filters of an Enhanced Beacon Request.",
+    )
+    .add_bit_field(
+        "Permit_joining_on",
+        "Specifies if only devices permitting association respond.",
+        layout.flag("permit_joining_on", 1),
+        |v| {
+            v.add_enum_value("any", 0)
+                .add_enum_value("permit_joining", 1)
+        },
+    )
+    .add_bit_field(
+        "Include_link_quality",
+        "Specifies if the link quality filter is included.",
+        layout.flag("include_link_quality", 1),
+        |v| v.add_enum_value("none", 0).add_enum_value("present", 1),
+    )
+    .add_bit_field(
+        "Include_percent_filter",
+        "Specifies if the percent filter is included.",
+        layout.flag("include_percent_filter", 1),
+        |v| v.add_enum_value("none", 0).add_enum_value("present", 1),
+    )
+    .add_bit_field(
+        "Attribute_id_count",
+        "The number of entries in the list of PIB attribute IDs.",
+        layout.field("attribute_id_count", 2),
+        |v| v.numeric(),
+    )
+    .add_reserved(layout.reserved(3));
+
+    genfile.add_bitfield(&bitfield)?;
+
+    Ok(layout)
+}
+
+/// The layouts of the IE descriptors, see `render_frame::render_layout`.
+pub(crate) fn layouts() -> Result<Vec<Layout>> {
+    let mut genfile = GenFile::new();
+
+    let (short, long) = nested_ie_control(&mut genfile)?;
+
+    Ok(vec![
+        payload_ie_control(&mut genfile)?,
+        short,
+        long,
+        eb_filter_descriptor(&mut genfile)?,
+    ])
+}
@@ -1,24 +1,203 @@
-use std::process;
+use std::fmt::Write;
 
 use anyhow::Result;
 
-use prot2rust::generate::structure::Structure;
+use prot2rust::generate::structure::{
+    AlternativeOptions, Alternatives, SimpleStructure, Structure,
+};
 use prot2rust::{file::GenFile, generate::bitfield};
 
-pub fn render_mac_command_id() -> Result<()> {
+use crate::layout::Layout;
+
+/// A field of the payload of a MAC command.
+#[derive(Clone, Copy)]
+enum Field {
+    U8(&'static str),
+    /// A little endian 16 bit integer.
+    U16(&'static str),
+    /// An octet holding the bitfield declared as `bitfield`, decoded as `ty`, which is
+    /// either its layout or `u8`.
+    Bitfield {
+        name: &'static str,
+        bitfield: &'static str,
+        ty: &'static str,
+    },
+    /// The `channel_page` alternative of the Coordinator Realignment command.
+    ChannelPage,
+}
+
+impl Field {
+    fn name(&self) -> &'static str {
+        match *self {
+            Field::U8(name) | Field::U16(name) | Field::Bitfield { name, .. } => name,
+            Field::ChannelPage => "channel_page",
+        }
+    }
+
+    /// The type of the field in `MacCommand`.
+    fn ty(&self) -> &'static str {
+        match *self {
+            Field::U8(_) => "u8",
+            Field::U16(_) => "u16",
+            Field::Bitfield { ty, .. } => ty,
+            Field::ChannelPage => "Option<u8>",
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Field::U16(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// A MAC command decoded by `MacCommand`.
+struct Command {
+    /// The name of the payload structure and of the command identifier.
+    name: &'static str,
+    /// The name of the `MacCommand` variant.
+    variant: &'static str,
+    description: &'static str,
+    id: u8,
+    fields: &'static [Field],
+}
+
+impl Command {
+    fn has_channel_page(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|field| matches!(field, Field::ChannelPage))
+    }
+}
+
+/// The MAC commands with a fixed layout, declaring both their payload structures and
+/// their command identifiers. The commands are decoded by the rendered `MacCommand`.
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "assoc_request",
+        variant: "AssocRequest",
+        description: "Association request command",
+        id: 0x01,
+        fields: &[Field::Bitfield {
+            name: "capability",
+            bitfield: "assoc_request_capability",
+            ty: "CapabilityInformation",
+        }],
+    },
+    Command {
+        name: "assoc_response",
+        variant: "AssocResponse",
+        description: "Association response command",
+        id: 0x02,
+        fields: &[
+            Field::U16("short_address"),
+            Field::Bitfield {
+                name: "status",
+                bitfield: "assoc_status",
+                ty: "u8",
+            },
+        ],
+    },
+    Command {
+        name: "disassoc_notify",
+        variant: "DisassocNotify",
+        description: "Disassociation Notification command",
+        id: 0x03,
+        fields: &[Field::Bitfield {
+            name: "reason",
+            bitfield: "disassoc_reason",
+            ty: "u8",
+        }],
+    },
+    // commands without any payload besides the command identifier
+    Command {
+        name: "data_request",
+        variant: "DataRequest",
+        description: "Data request command",
+        id: 0x04,
+        fields: &[],
+    },
+    Command {
+        name: "pan_id_conflict",
+        variant: "PanIdConflict",
+        description: "PAN ID Conflict Notification command",
+        id: 0x05,
+        fields: &[],
+    },
+    Command {
+        name: "orphan_notify",
+        variant: "OrphanNotify",
+        description: "Orphan notification command",
+        id: 0x06,
+        fields: &[],
+    },
+    Command {
+        name: "beacon_request",
+        variant: "BeaconRequest",
+        description: "Beacon request command",
+        id: 0x07,
+        fields: &[],
+    },
+    Command {
+        name: "coordinator_realign",
+        variant: "CoordinatorRealign",
+        description: "Coordinator Realignment command",
+        id: 0x08,
+        fields: &[
+            Field::U16("pan_id"),
+            Field::U16("coordinator_short_address"),
+            Field::U8("channel_number"),
+            Field::U16("short_address"),
+            Field::ChannelPage,
+        ],
+    },
+    Command {
+        name: "gts_request",
+        variant: "GtsRequest",
+        description: "GTS request command",
+        id: 0x09,
+        fields: &[Field::Bitfield {
+            name: "characteristics",
+            bitfield: "gts_characteristics",
+            ty: "GtsCharacteristics",
+        }],
+    },
+];
+
+/// The values of the association status, with their descriptions.
+const ASSOCIATION_STATUS: &[(&str, &str, u8)] = &[
+    ("assoc_success", "Association successful.", 0),
+    ("pan_at_capacity", "The PAN is at capacity.", 1),
+    ("pan_access_denied", "PAN access denied.", 2),
+    (
+        "hopping_duplication",
+        "Hopping sequence offset duplication.",
+        3,
+    ),
+    ("fast_assoc_success", "Fast association successful.", 0x80),
+];
+
+/// The values of the disassociation reason, with their descriptions.
+const DISASSOCIATION_REASON: &[(&str, &str, u8)] = &[
+    (
+        "coordinator_leave",
+        "The coordinator wishes the device to leave the PAN.",
+        1,
+    ),
+    ("device_leave", "The device wishes to leave the PAN.", 2),
+];
+
+pub fn render_mac_command_id() -> Result<GenFile> {
     let mut genfile = GenFile::new();
 
     let bitfield = bitfield::BitField::new("MAC command", "The MAC command identifier")
         .add_bit_field("id", "The MAC command identifier.", 8, |v| {
-            v.add_enum_value_desc("assoc_request", "Association request command", 1)
-                .add_enum_value_desc("assoc_response", "Association response command", 2)
-                .add_enum_value_desc("dissasoc_notify", "Dissassociation Notification command", 3)
-                .add_enum_value_desc("data_request", "Data request command", 4)
-                .add_enum_value_desc("pan_id_conflict", "PAN ID Conflict Notification command", 5)
-                .add_enum_value_desc("orphan_notify", "Orphan notification command", 6)
-                .add_enum_value_desc("beacon_request", "Beacon request command", 7)
-                .add_enum_value_desc("coordinator_realign", "Coordinator Realignment command", 8)
-                .add_enum_value_desc("gts_request", "GTS request command", 9)
+            COMMANDS
+                .iter()
+                .fold(v, |v, command| {
+                    v.add_enum_value_desc(command.name, command.description, command.id.into())
+                })
                 .add_enum_value_desc("trle_mgmt_request", "TRLE Management Request command", 0x0a)
                 .add_enum_value_desc(
                     "trle_mgmt_response",
@@ -79,30 +258,43 @@ pub fn render_mac_command_id() -> Result<()> {
 
     genfile.add_bitfield(&bitfield)?;
 
-    genfile.write_file("out/mac_command/command_id.rs")
+    Ok(genfile)
 }
 
-pub fn render_association_request_capability() -> Result<()> {
+pub fn render_association_request_capability() -> Result<GenFile> {
     let mut genfile = GenFile::new();
+    association_request_capability(&mut genfile)?;
+
+    Ok(genfile)
+}
+
+/// Declares the capability information of the Association Request command, returning
+/// its layout.
+fn association_request_capability(genfile: &mut GenFile) -> Result<Layout> {
+    let mut layout = Layout::new(
+        "CapabilityInformation",
+        "The capability information of the Association Request command, see `Capability` \
+         for the description of the fields.",
+    );
 
     let bitfield = bitfield::BitField::new("Capability", "Association request capabilities")
-        .add_reserved(1)
-        .add_bit_field("device_type", "Set to one if the device is an FFD, otherwise it is an RFD.", 1, |v| v.add_enum_value("ffd_device", 1).add_enum_value("rfd_device", 0))
-        .add_bit_field("power_source", "Set to one if the device is connected to Alternating Current, otherwise it is a battery device.", 1, |v| v.add_enum_value_desc("mains_powered", "The device is connected to alternative current mains.", 1).add_enum_value_desc("battery_powered", "The device is powered by a battery pack.", 0))
-        .add_bit_field("receiver_on_when_idle", "The device does not disable its receiver to conserve power during idle periods.", 1, |v|
+        .add_reserved(layout.reserved(1))
+        .add_bit_field("device_type", "Set to one if the device is an FFD, otherwise it is an RFD.", layout.flag("ffd", 1), |v| v.add_enum_value("ffd_device", 1).add_enum_value("rfd_device", 0))
+        .add_bit_field("power_source", "Set to one if the device is connected to Alternating Current, otherwise it is a battery device.", layout.flag("mains_powered", 1), |v| v.add_enum_value_desc("mains_powered", "The device is connected to alternative current mains.", 1).add_enum_value_desc("battery_powered", "The device is powered by a battery pack.", 0))
+        .add_bit_field("receiver_on_when_idle", "The device does not disable its receiver to conserve power during idle periods.", layout.flag("receiver_on_when_idle", 1), |v|
                        v.add_enum_value_desc("receives_on_idle", "The device does not disable its receiver during idle periods.", 1)
                        .add_enum_value_desc("disables_on_idle", "The device disables its receiver to conserve power during idle periods.", 0))
-        .add_bit_field("association_type", "Set to one if the device requests fast association.", 1, |v| v.add_enum_value("fast_association", 1).add_enum_value("slow_association", 0))
-        .add_reserved(1)
-        .add_bit_field("security_capability", "Determines if the device is capable of sending and receiving cryptographically protected MAC frames.", 1, |v| v.add_enum_value_desc("secure", "The device is capable of sending and receiving cryptographically protected MAC frames.", 1).add_enum_value_desc("unsecure", "The device is incapable of sending and receiving cryptographically protected MAC frames.", 0))
-        .add_bit_field("allocate_address", "Determines if the coordinator should allocate a short address as a result of the allocation procedure.", 1, |v| v.add_enum_value_desc("request_address", "The device wishes the coordinator to allocate a short address.", 1).add_enum_value_desc("no_request", "The device does not request the coordinator to allocate a short address.", 0));
+        .add_bit_field("association_type", "Set to one if the device requests fast association.", layout.flag("fast_association", 1), |v| v.add_enum_value("fast_association", 1).add_enum_value("slow_association", 0))
+        .add_reserved(layout.reserved(1))
+        .add_bit_field("security_capability", "Determines if the device is capable of sending and receiving cryptographically protected MAC frames.", layout.flag("security_capability", 1), |v| v.add_enum_value_desc("secure", "The device is capable of sending and receiving cryptographically protected MAC frames.", 1).add_enum_value_desc("unsecure", "The device is incapable of sending and receiving cryptographically protected MAC frames.", 0))
+        .add_bit_field("allocate_address", "Determines if the coordinator should allocate a short address as a result of the allocation procedure.", layout.flag("allocate_address", 1), |v| v.add_enum_value_desc("request_address", "The device wishes the coordinator to allocate a short address.", 1).add_enum_value_desc("no_request", "The device does not request the coordinator to allocate a short address.", 0));
 
     genfile.add_bitfield(&bitfield)?;
 
-    genfile.write_file("out/mac_command/assoc_request_capability.rs")
+    Ok(layout)
 }
 
-pub fn render_association_status() -> Result<()> {
+pub fn render_association_status() -> Result<GenFile> {
     let mut genfile = GenFile::new();
 
     let bitfield = bitfield::BitField::new("Assoc_status", "Association status").add_bit_field(
@@ -110,43 +302,379 @@ pub fn render_association_status() -> Result<()> {
         "The association status after a request.",
         8,
         |v| {
-            v.add_enum_value_desc("assoc_success", "Association successful.", 0)
-                .add_enum_value_desc("pan_at_capacity", "The PAN is at capacity.", 1)
-                .add_enum_value_desc("pan_access_denied", "PAN access denied.", 2)
-                .add_enum_value_desc(
-                    "hopping_duplication",
-                    "Hopping sequence offset duplication.",
-                    3,
-                )
-                .add_enum_value_desc("fast_assoc_success", "Fast association successful.", 0x80)
+            ASSOCIATION_STATUS
+                .iter()
+                .fold(v, |v, &(name, description, value)| {
+                    v.add_enum_value_desc(name, description, value.into())
+                })
         },
     );
 
     genfile.add_bitfield(&bitfield)?;
 
-    genfile.write_file("out/mac_command/assoc_status.rs")
+    Ok(genfile)
+}
+
+pub fn render_disassociation_reason() -> Result<GenFile> {
+    let mut genfile = GenFile::new();
+
+    let bitfield = bitfield::BitField::new("Disassoc_reason", "Disassociation reason")
+        .add_bit_field(
+            "disassociation_reason",
+            "The reason for the disassociation.",
+            8,
+            |v| {
+                DISASSOCIATION_REASON
+                    .iter()
+                    .fold(v, |v, &(name, description, value)| {
+                        v.add_enum_value_desc(name, description, value.into())
+                    })
+            },
+        );
+
+    genfile.add_bitfield(&bitfield)?;
+
+    Ok(genfile)
+}
+
+pub fn render_gts_characteristics() -> Result<GenFile> {
+    let mut genfile = GenFile::new();
+    gts_characteristics(&mut genfile)?;
+
+    Ok(genfile)
+}
+
+/// Declares the characteristics of the GTS request command, returning their layout.
+fn gts_characteristics(genfile: &mut GenFile) -> Result<Layout> {
+    let mut layout = Layout::new(
+        "GtsCharacteristics",
+        "The characteristics of the GTS request command, see `GTS_characteristics` for the \
+         description of the fields.",
+    );
+
+    let bitfield = bitfield::BitField::new("GTS_characteristics", "GTS request characteristics")
+        .add_bit_field(
+            "gts_length",
+            "The number of superframe slots being requested for the GTS.",
+            layout.field("gts_length", 4),
+            |v| v.numeric(),
+        )
+        .add_bit_field(
+            "gts_direction",
+            "The direction of the GTS.",
+            layout.flag("receive_only", 1),
+            |v| {
+                v.add_enum_value_desc("transmit_only", "The GTS is used to transmit data.", 0)
+                    .add_enum_value_desc("receive_only", "The GTS is used to receive data.", 1)
+            },
+        )
+        .add_bit_field(
+            "characteristics_type",
+            "Specifies if the request allocates or deallocates a GTS.",
+            layout.flag("allocation", 1),
+            |v| {
+                v.add_enum_value_desc("deallocation", "The GTS is to be deallocated.", 0)
+                    .add_enum_value_desc("allocation", "A new GTS is to be allocated.", 1)
+            },
+        )
+        .add_reserved(layout.reserved(2));
+
+    genfile.add_bitfield(&bitfield)?;
+
+    Ok(layout)
 }
 
-pub fn render_commands() -> Result<()> {
+/// Selects the `channel_page` alternative of the Coordinator Realignment command,
+/// rendered into the command frame.
+const CHANNEL_PAGE_PRESENT: &str = "
+/// Determines if the channel page of a Coordinator Realignment command in a frame of
+/// `frame_version` is present at `offset` of `buf`, selecting the `channel_page`
+/// alternative. Frames of version 2003 do not include the channel page, frames of later
+/// versions include it unless the payload ends before it.
+fn channel_page_present(frame_version: u8, buf: &[u8], offset: usize) -> bool {
+    frame_version != frame_version::VERSION_2003 && buf.len() > offset
+}
+";
+
+pub fn render_commands() -> Result<GenFile> {
     let mut genfile = GenFile::new();
 
     genfile.add_struct_imports()?;
 
-    let assoc_request =
-        Structure::new("assoc_request").add_bitfield("capability", "assoc_request_capability", 1);
+    // the channel page is selected by the frame version and the length of the payload,
+    // see `CHANNEL_PAGE_PRESENT`
+    let channel_page_none = Structure::new("channel_page_none");
+    let channel_page_present = SimpleStructure::new("channel_page_present", "channel_page", 1);
+
+    genfile.add_struct(&channel_page_none)?;
+    genfile.add_struct_simple(&channel_page_present)?;
+
+    let channel_page = AlternativeOptions::new("channel_page", &channel_page_none)
+        .insert_type(&channel_page_present);
+
+    let alternatives = Alternatives::new().insert(&channel_page);
+    genfile.add_alternatives(&alternatives)?;
+
+    for command in COMMANDS {
+        let structure = command.fields.iter().fold(
+            Structure::new(command.name),
+            |structure, field| match *field {
+                Field::U8(name) => structure.add_u8_field(name),
+                Field::U16(name) => structure.add_u16_field(name),
+                Field::Bitfield { name, bitfield, .. } => structure.add_bitfield(name, bitfield, 1),
+                Field::ChannelPage => structure.add_alt_field("channel_page", &channel_page),
+            },
+        );
+
+        if command.has_channel_page() {
+            genfile.add_struct_with_alts(&structure, &alternatives)?;
+        } else {
+            genfile.add_struct(&structure)?;
+        }
+    }
+
+    Ok(genfile)
+}
+
+/// The layouts of the bitfields of the command payloads, see
+/// `render_frame::render_layout`.
+pub(crate) fn layouts() -> Result<Vec<Layout>> {
+    let mut genfile = GenFile::new();
+
+    Ok(vec![
+        association_request_capability(&mut genfile)?,
+        gts_characteristics(&mut genfile)?,
+    ])
+}
+
+/// Renders `MacCommand`, which decodes the commands of `COMMANDS`, between the hand
+/// written prelude and tests of the command frame.
+pub fn render_command_frame() -> Result<String> {
+    let mut out = String::from(include_str!("../templates/mac_command/frame.rs"));
+
+    writeln!(
+        out,
+        "\n/// The command identifiers of the decoded commands, see `MAC command`."
+    )?;
+    writeln!(out, "pub mod command_ids {{")?;
+    for command in COMMANDS {
+        let name = command.name.to_uppercase();
+        writeln!(out, "    pub const {}: u8 = {:#04x};", name, command.id)?;
+    }
+    writeln!(out, "}}")?;
+
+    render_values(
+        &mut out,
+        "association_status",
+        "The association status of the Association Response command, see `Assoc_status`.",
+        ASSOCIATION_STATUS,
+    )?;
+    render_values(
+        &mut out,
+        "disassociation_reason",
+        "The reason of the Disassociation Notification command, see `Disassoc_reason`.",
+        DISASSOCIATION_REASON,
+    )?;
+
+    writeln!(
+        out,
+        "
+/// A decoded MAC command, see `commands` for the payload of each command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MacCommand<'a> {{"
+    )?;
+    for command in COMMANDS {
+        writeln!(out, "    /// {}.", command.description)?;
+        if command.fields.is_empty() {
+            writeln!(out, "    {},", command.variant)?;
+            continue;
+        }
+
+        let fields: Vec<String> = command
+            .fields
+            .iter()
+            .map(|field| format!("{}: {}", field.name(), field.ty()))
+            .collect();
+
+        // laid out as rustfmt does, short variants without documented fields on one line
+        if !command.has_channel_page() && fields.join(", ").len() <= 35 {
+            writeln!(out, "    {} {{ {} }},", command.variant, fields.join(", "))?;
+            continue;
+        }
+
+        writeln!(out, "    {} {{", command.variant)?;
+        for (field, declaration) in command.fields.iter().zip(&fields) {
+            if let Field::ChannelPage = field {
+                writeln!(
+                    out,
+                    "        /// Absent in frames of version 2003, and in later versions if the \
+                     payload\n        /// ends before it."
+                )?;
+            }
+            writeln!(out, "        {},", declaration)?;
+        }
+        writeln!(out, "    }},")?;
+    }
+    writeln!(
+        out,
+        "    /// A command which is not decoded, with its identifier and raw payload.
+    Unknown(u8, &'a [u8]),
+}}
+
+impl<'a> MacCommand<'a> {{
+    /// Decodes the command identifier and the payload following it in a frame of
+    /// `frame_version`. Fails if `buf` is empty, or if the payload of a decoded command
+    /// is truncated or longer than its fields.
+    pub fn parse(buf: &'a [u8], frame_version: u8) -> Result<Self, ParseError> {{
+        let id = get(buf, 0, 1)?[0];
 
-    let assoc_response = Structure::new("assoc_response")
-        .add_u16_field("short_address")
-        .add_bitfield("status", "assoc_status", 1);
+        let command = match id {{"
+    )?;
+    for command in COMMANDS {
+        let id = format!("command_ids::{}", command.name.to_uppercase());
+        if command.fields.is_empty() {
+            writeln!(
+                out,
+                "            {} => MacCommand::{},",
+                id, command.variant
+            )?;
+            continue;
+        }
+
+        writeln!(
+            out,
+            "            {} => MacCommand::{} {{",
+            id, command.variant
+        )?;
+        let mut offset = 1;
+        for field in command.fields {
+            let end = offset + field.len();
+            let value = match *field {
+                Field::U8(_) | Field::Bitfield { ty: "u8", .. } => {
+                    format!("get(buf, {}, {})?[0]", offset, end)
+                }
+                Field::U16(_) => format!(
+                    "u16::from_le_bytes(get(buf, {}, {})?.try_into().unwrap())",
+                    offset, end
+                ),
+                Field::Bitfield { ty, .. } => format!("{}(get(buf, {}, {})?[0])", ty, offset, end),
+                Field::ChannelPage => format!(
+                    "if channel_page_present(frame_version, buf, {}) {{
+                    Some(buf[{}])
+                }} else {{
+                    None
+                }}",
+                    offset, offset
+                ),
+            };
+            writeln!(out, "                {}: {},", field.name(), value)?;
+            offset = end;
+        }
+        writeln!(out, "            }},")?;
+    }
+    writeln!(
+        out,
+        "            _ => MacCommand::Unknown(id, &buf[1..]),
+        }};
+
+        let len = command.buffer_len();
+        if buf.len() > len {{
+            return Err(ParseError::InvalidField {{ offset: len }});
+        }}
+
+        Ok(command)
+    }}
+
+    /// The command identifier of the command.
+    pub fn command_id(&self) -> u8 {{
+        match self {{"
+    )?;
+    for command in COMMANDS {
+        writeln!(
+            out,
+            "            MacCommand::{}{} => command_ids::{},",
+            command.variant,
+            if command.fields.is_empty() {
+                ""
+            } else {
+                " { .. }"
+            },
+            command.name.to_uppercase()
+        )?;
+    }
+    writeln!(
+        out,
+        "            MacCommand::Unknown(id, _) => *id,
+        }}
+    }}
+
+    /// The encoded length of the command, including the command identifier.
+    pub fn buffer_len(&self) -> usize {{
+        match self {{"
+    )?;
+    for command in COMMANDS {
+        let len: usize = 1 + command.fields.iter().map(Field::len).sum::<usize>();
+        if command.fields.is_empty() {
+            writeln!(
+                out,
+                "            MacCommand::{} => {},",
+                command.variant, len
+            )?;
+        } else if command.has_channel_page() {
+            writeln!(
+                out,
+                "            MacCommand::{} {{ channel_page, .. }} => {{
+                {} + usize::from(channel_page.is_some())
+            }}",
+                command.variant,
+                len - 1
+            )?;
+        } else {
+            writeln!(
+                out,
+                "            MacCommand::{} {{ .. }} => {},",
+                command.variant, len
+            )?;
+        }
+    }
+    writeln!(
+        out,
+        "            MacCommand::Unknown(_, payload) => 1 + payload.len(),
+        }}
+    }}
+}}"
+    )?;
+
+    out.push_str(CHANNEL_PAGE_PRESENT);
+    out.push('\n');
+    out.push_str(include_str!("../templates/mac_command/frame_tests.rs"));
+
+    Ok(out)
+}
 
-    genfile.add_struct(&assoc_request)?;
+/// Renders `values` as constants of the module `module`.
+fn render_values(
+    out: &mut String,
+    module: &str,
+    doc: &str,
+    values: &[(&str, &str, u8)],
+) -> Result<()> {
+    writeln!(out, "\n/// {}", doc)?;
+    writeln!(out, "pub mod {} {{", module)?;
+    for &(name, description, value) in values {
+        writeln!(out, "    /// {}", description)?;
+        writeln!(
+            out,
+            "    pub const {}: u8 = {:#04x};",
+            name.to_uppercase(),
+            value
+        )?;
+    }
+    writeln!(out, "}}")?;
 
-    genfile.write_file("out/mac_command/commands.rs")
+    Ok(())
 }
 
-pub fn render() -> Result<()> {
-    render_mac_command_id()?;
-    render_association_request_capability()?;
-    render_association_status()?;
-    render_commands()
+pub fn render_enhanced_beacon_request() -> &'static str {
+    include_str!("../templates/mac_command/enhanced_beacon_request.rs")
 }
@@ -8,8 +8,21 @@ use prot2rust::{
     },
 };
 
-pub fn render_security_control() -> Result<()> {
+use crate::layout::Layout;
+
+pub fn render_security_control() -> Result<GenFile> {
     let mut genfile = GenFile::new();
+    security_control(&mut genfile)?;
+
+    Ok(genfile)
+}
+
+/// Declares the security control field, returning its layout.
+fn security_control(genfile: &mut GenFile) -> Result<Layout> {
+    let mut layout = Layout::new(
+        "SecurityControl",
+        "The security control field, see `Security_control` for the description of the fields.",
+    );
 
     let bitfield = bitfield::BitField::new(
         "Security_control",
@@ -18,7 +31,7 @@ pub fn render_security_control() -> Result<()> {
     .add_bit_field(
         "Security_level",
         "Indicates the actual frame protection that is provided",
-        3,
+        layout.field("security_level", 3),
         |v| {
             v.add_enum_value_desc("NONE", "Security level 0, no encryption.", 0b000)
                 .add_enum_value_desc("MIC_32", "Security level 1, uses a 4 byte MIC for data authenticity.", 0b001)
@@ -32,7 +45,11 @@ pub fn render_security_control() -> Result<()> {
     .add_bit_field(
         "Key_identifier_mode",
         "Specifies whether the key that is used to protect the frame can be derived implicitly or explicitly.",
-        2,
+        layout.selecting_field(
+            "key_id_mode",
+            2,
+            "Selects the `key_identifier` alternative, see `key_identifier_len`.",
+        ),
         |v| {
             v.add_enum_value_desc("implicit", "Key is determined implicitly.", 0b00)
                 .add_enum_value_desc("key_index", "Key is determined from the key index field.", 0b01)
@@ -41,26 +58,37 @@ pub fn render_security_control() -> Result<()> {
         })
     .add_bit_field(
         "Frame_counter_suppresion",
-        "Specifies if the frame counter should be suppressed from the frame.",
-        1,
+        "Specifies if the frame counter should be suppressed from the frame. Reserved in frame version 2006, where the frame counter is always present.",
+        layout.selecting_flag(
+            "frame_counter_suppressed",
+            1,
+            "Selects the `frame_counter_type` alternative, see `frame_counter_present`.",
+        ),
         |v| v.add_enum_value_desc("present", "The frame counter is included in the frame.", 0)
             .add_enum_value_desc("suppressed", "The frame counter is suppressed from the frame.", 1))
     .add_bit_field(
         "ASN_in_nonce",
-        "Specifies if the absolute number slot (ASN) is used to generate the Nonce.",
-        1,
+        "Specifies if the absolute number slot (ASN) is used to generate the Nonce. Reserved in frame version 2006.",
+        layout.flag("asn_in_nonce", 1),
         |v| v.add_enum_value_desc("frame_counter_nonce", "The frame counter is used to generate the Nonce.", 0)
             .add_enum_value_desc("asn_nonce", "The ASN is used to generate the Nonce.", 1))
-    .add_reserved(1);
+    .add_reserved(layout.reserved(1));
 
     genfile.add_bitfield(&bitfield)?;
 
-    genfile.write_file("out/security_control.rs")
+    Ok(layout)
 }
 
-pub fn render_auxiliary_security_header() -> Result<()> {
+pub fn render_auxiliary_security_header() -> Result<GenFile> {
     let mut genfile = GenFile::new();
+    auxiliary_security_header(&mut genfile, &mut security_control(&mut GenFile::new())?)?;
 
+    Ok(genfile)
+}
+
+/// Declares the auxiliary security header and its alternatives, adding the selection of
+/// the alternatives to the layout of the security control field.
+fn auxiliary_security_header(genfile: &mut GenFile, security_control: &mut Layout) -> Result<()> {
     genfile.add_struct_imports()?;
 
     let frame_counter_none = Structure::new("frame_counter_none");
@@ -71,6 +99,14 @@ pub fn render_auxiliary_security_header() -> Result<()> {
 
     let frame_counter = AlternativeOptions::new("frame_counter_type", &frame_counter_none)
         .insert_type(&frame_counter_present);
+    security_control.selector(
+        "Determines if the frame counter is present in the auxiliary security header,
+selecting between the `frame_counter_none` and `frame_counter_present` alternatives.
+The security control field is expected to be adjusted to the frame version, see
+`SecurityControl::for_version`.",
+        "fn frame_counter_present(&self) -> bool",
+        "!self.frame_counter_suppressed()",
+    );
 
     let key_id_none = Structure::new("key_id_none");
     let key_id_only = SimpleStructure::new("key_id_only", "key_id", 1);
@@ -90,10 +126,24 @@ pub fn render_auxiliary_security_header() -> Result<()> {
         .insert_type(&key_id_only)
         .insert_type(&key_id_short)
         .insert_type(&key_id_long);
+    security_control.selector(
+        "The length of the key identifier, selecting the `key_identifier` alternative by the
+key identifier mode.",
+        "fn key_identifier_len(&self) -> usize",
+        "
+match self.key_id_mode() {
+    0b00 => 0,
+    0b01 => 1,
+    0b10 => 5,
+    _ => 9,
+}",
+    );
 
     let alternatives = Alternatives::new().insert(&frame_counter).insert(&key_id);
     genfile.add_alternatives(&alternatives)?;
 
+    // the alternatives are selected by the security control field, see
+    // `security_control`
     let structure = structure::Structure::new("Auxiliary_security_header")
         .add_bitfield("security_control", "security_control", 1)
         .add_alt_field("frame_counter", &frame_counter)
@@ -101,5 +151,48 @@ pub fn render_auxiliary_security_header() -> Result<()> {
 
     genfile.add_struct_with_alts(&structure, &alternatives)?;
 
-    genfile.write_file("out/auxiliary_security_header.rs")
+    Ok(())
+}
+
+/// The layout of the security control field with the selection of the alternatives of
+/// the auxiliary security header, see `render_frame::render_layout`.
+pub(crate) fn layouts() -> Result<Vec<Layout>> {
+    let mut genfile = GenFile::new();
+
+    let mut security_control = security_control(&mut genfile)?;
+    auxiliary_security_header(&mut genfile, &mut security_control)?;
+
+    Ok(vec![security_control])
+}
+
+pub fn render_security_header() -> &'static str {
+    include_str!("../templates/security/header.rs")
+}
+
+pub fn render_aes() -> &'static str {
+    include_str!("../templates/security/aes.rs")
+}
+
+pub fn render_ccm() -> &'static str {
+    include_str!("../templates/security/ccm.rs")
+}
+
+pub fn render_security_frame() -> &'static str {
+    include_str!("../templates/security/frame.rs")
+}
+
+pub fn render_security_pib() -> &'static str {
+    include_str!("../templates/security/pib.rs")
+}
+
+pub fn render_frame_counters() -> &'static str {
+    include_str!("../templates/security/counter.rs")
+}
+
+pub fn render_incoming_security() -> &'static str {
+    include_str!("../templates/security/incoming.rs")
+}
+
+pub fn render_outgoing_security() -> &'static str {
+    include_str!("../templates/security/outgoing.rs")
 }
@@ -0,0 +1,394 @@
+use anyhow::Result;
+
+use prot2rust::file::GenFile;
+
+use crate::{render_beacon, render_frame, render_ie, render_mac_command, render_security};
+
+/// A single generated file.
+pub struct Target {
+    /// The group the target belongs to, e.g. `beacon`.
+    pub group: &'static str,
+    /// The name of the target, which is the name of its `render_*` function.
+    pub name: &'static str,
+    /// The path of the generated file, relative to the output directory.
+    pub path: &'static str,
+    pub render: Render,
+    /// The names of the targets the generated file refers to.
+    pub requires: &'static [&'static str],
+}
+
+/// Produces the contents of a target.
+#[derive(Clone, Copy)]
+pub enum Render {
+    /// Declarations generated by prot2rust.
+    Generated(fn() -> Result<GenFile>),
+    /// Hand written code, copied verbatim. It only refers to the targets it requires,
+    /// never to the generated declarations, so it can be generated without them.
+    Source(fn() -> &'static str),
+    /// Code rendered by the generator from the declarations it passes to prot2rust,
+    /// such as the layouts of bitfields. Like hand written code it does not refer to
+    /// the generated declarations.
+    Rendered(fn() -> Result<String>),
+}
+
+impl Target {
+    /// Determines if the target is selected by `filter`, which is either a group name,
+    /// a target name or `group/name`.
+    pub fn matches(&self, filter: &str) -> bool {
+        match filter.split_once('/') {
+            Some((group, name)) => group == self.group && name == self.name,
+            None => filter == self.group || filter == self.name,
+        }
+    }
+}
+
+pub const TARGETS: &[Target] = &[
+    Target {
+        group: "frame",
+        name: "frame_control",
+        path: "frame_control.rs",
+        render: Render::Generated(render_frame::render_frame_control),
+        requires: &[],
+    },
+    Target {
+        group: "frame",
+        name: "mac",
+        path: "mac_frame.rs",
+        render: Render::Generated(render_frame::render_mac),
+        requires: &[],
+    },
+    Target {
+        group: "frame",
+        name: "ie_control",
+        path: "ie_control.rs",
+        render: Render::Generated(render_frame::render_ie_control),
+        requires: &[],
+    },
+    Target {
+        group: "frame",
+        name: "layout",
+        path: "layout.rs",
+        render: Render::Rendered(render_frame::render_layout),
+        requires: &[],
+    },
+    Target {
+        group: "frame",
+        name: "parse_error",
+        path: "error.rs",
+        render: Render::Source(render_frame::render_parse_error),
+        requires: &[],
+    },
+    Target {
+        group: "frame",
+        name: "header",
+        path: "header.rs",
+        render: Render::Source(render_frame::render_header),
+        requires: &["layout", "parse_error"],
+    },
+    Target {
+        group: "frame",
+        name: "data_frame",
+        path: "data_frame.rs",
+        render: Render::Source(render_frame::render_data_frame),
+        requires: &["fcs", "header", "parse_error", "security_header"],
+    },
+    Target {
+        group: "frame",
+        name: "ack_frame",
+        path: "ack_frame.rs",
+        render: Render::Source(render_frame::render_ack_frame),
+        requires: &["fcs", "header", "parse_error"],
+    },
+    Target {
+        group: "frame",
+        name: "fcs",
+        path: "fcs.rs",
+        render: Render::Source(render_frame::render_fcs),
+        requires: &[],
+    },
+    Target {
+        group: "frame",
+        name: "frame",
+        path: "frame.rs",
+        render: Render::Source(render_frame::render_frame),
+        requires: &["fcs", "header", "parse_error", "security_header"],
+    },
+    Target {
+        group: "frame",
+        name: "frame_builder",
+        path: "builder.rs",
+        render: Render::Source(render_frame::render_frame_builder),
+        requires: &["header", "header_ies", "payload_ies", "security_header"],
+    },
+    Target {
+        group: "ie",
+        name: "header_ies",
+        path: "ie/header.rs",
+        render: Render::Source(render_ie::render_header_ies),
+        requires: &["layout", "parse_error"],
+    },
+    Target {
+        group: "ie",
+        name: "header_ie_content",
+        path: "ie/content.rs",
+        render: Render::Source(render_ie::render_header_ie_content),
+        requires: &["header_ies", "parse_error"],
+    },
+    Target {
+        group: "ie",
+        name: "payload_ie_control",
+        path: "ie/payload_ie_control.rs",
+        render: Render::Generated(render_ie::render_payload_ie_control),
+        requires: &[],
+    },
+    Target {
+        group: "ie",
+        name: "nested_ie_control",
+        path: "ie/nested_ie_control.rs",
+        render: Render::Generated(render_ie::render_nested_ie_control),
+        requires: &[],
+    },
+    Target {
+        group: "ie",
+        name: "payload_ies",
+        path: "ie/payload.rs",
+        render: Render::Source(render_ie::render_payload_ies),
+        requires: &["header_ies", "layout", "parse_error"],
+    },
+    Target {
+        group: "ie",
+        name: "tsch_ies",
+        path: "ie/tsch.rs",
+        render: Render::Source(render_ie::render_tsch_ies),
+        requires: &["parse_error", "payload_ies"],
+    },
+    Target {
+        group: "ie",
+        name: "frame_ies",
+        path: "ie/list.rs",
+        render: Render::Source(render_ie::render_frame_ies),
+        requires: &["header_ies", "parse_error", "payload_ies"],
+    },
+    Target {
+        group: "ie",
+        name: "eb_filter_descriptor",
+        path: "ie/eb_filter_descriptor.rs",
+        render: Render::Generated(render_ie::render_eb_filter_descriptor),
+        requires: &[],
+    },
+    Target {
+        group: "security",
+        name: "security_control",
+        path: "security_control.rs",
+        render: Render::Generated(render_security::render_security_control),
+        requires: &[],
+    },
+    Target {
+        group: "security",
+        name: "auxiliary_security_header",
+        path: "auxiliary_security_header.rs",
+        render: Render::Generated(render_security::render_auxiliary_security_header),
+        requires: &[],
+    },
+    Target {
+        group: "security",
+        name: "security_header",
+        path: "security/header.rs",
+        render: Render::Source(render_security::render_security_header),
+        requires: &["header", "layout", "parse_error"],
+    },
+    Target {
+        group: "security",
+        name: "aes",
+        path: "security/aes.rs",
+        render: Render::Source(render_security::render_aes),
+        requires: &[],
+    },
+    Target {
+        group: "security",
+        name: "ccm",
+        path: "security/ccm.rs",
+        render: Render::Source(render_security::render_ccm),
+        requires: &["aes"],
+    },
+    Target {
+        group: "security",
+        name: "security_frame",
+        path: "security/frame.rs",
+        render: Render::Source(render_security::render_security_frame),
+        requires: &[
+            "header",
+            "parse_error",
+            "security_header",
+            "header_ies",
+            "ccm",
+        ],
+    },
+    Target {
+        group: "security",
+        name: "security_pib",
+        path: "security/pib.rs",
+        render: Render::Source(render_security::render_security_pib),
+        requires: &["header", "parse_error", "security_header", "ccm"],
+    },
+    Target {
+        group: "security",
+        name: "frame_counters",
+        path: "security/counter.rs",
+        render: Render::Source(render_security::render_frame_counters),
+        requires: &["security_pib"],
+    },
+    Target {
+        group: "security",
+        name: "incoming_security",
+        path: "security/incoming.rs",
+        render: Render::Source(render_security::render_incoming_security),
+        requires: &["ccm", "frame_counters", "security_frame", "security_pib"],
+    },
+    Target {
+        group: "security",
+        name: "outgoing_security",
+        path: "security/outgoing.rs",
+        render: Render::Source(render_security::render_outgoing_security),
+        requires: &[
+            "ccm",
+            "frame_counters",
+            "header",
+            "security_frame",
+            "security_header",
+            "security_pib",
+        ],
+    },
+    Target {
+        group: "beacon",
+        name: "superframe",
+        path: "beacon/superframe.rs",
+        render: Render::Generated(render_beacon::render_superframe),
+        requires: &[],
+    },
+    Target {
+        group: "beacon",
+        name: "gts_specification",
+        path: "beacon/gts_specification.rs",
+        render: Render::Generated(render_beacon::render_gts_specification),
+        requires: &[],
+    },
+    Target {
+        group: "beacon",
+        name: "gts_direction",
+        path: "beacon/gts_directions.rs",
+        render: Render::Generated(render_beacon::render_gts_direction),
+        requires: &[],
+    },
+    Target {
+        group: "beacon",
+        name: "gts_descriptor_config",
+        path: "beacon/gts_descriptor_config.rs",
+        render: Render::Generated(render_beacon::render_gts_descriptor_config),
+        requires: &[],
+    },
+    Target {
+        group: "beacon",
+        name: "gts_descriptor",
+        path: "beacon/gts_descriptor.rs",
+        render: Render::Generated(render_beacon::render_gts_descriptor),
+        requires: &[],
+    },
+    Target {
+        group: "beacon",
+        name: "gts_info",
+        path: "beacon/gts_info.rs",
+        render: Render::Generated(render_beacon::render_gts_info),
+        requires: &[],
+    },
+    Target {
+        group: "beacon",
+        name: "pending_address_spec",
+        path: "beacon/pending_address_specifications.rs",
+        render: Render::Generated(render_beacon::render_pending_address_spec),
+        requires: &[],
+    },
+    Target {
+        group: "beacon",
+        name: "beacon_payload",
+        path: "beacon/payload.rs",
+        render: Render::Source(render_beacon::render_beacon_payload),
+        requires: &["parse_error"],
+    },
+    Target {
+        group: "beacon",
+        name: "enhanced_beacon",
+        path: "beacon/enhanced.rs",
+        render: Render::Source(render_beacon::render_enhanced_beacon),
+        requires: &[
+            "fcs",
+            "header",
+            "frame_ies",
+            "parse_error",
+            "security_header",
+        ],
+    },
+    Target {
+        group: "mac_command",
+        name: "mac_command_id",
+        path: "mac_command/command_id.rs",
+        render: Render::Generated(render_mac_command::render_mac_command_id),
+        requires: &[],
+    },
+    Target {
+        group: "mac_command",
+        name: "association_request_capability",
+        path: "mac_command/assoc_request_capability.rs",
+        render: Render::Generated(render_mac_command::render_association_request_capability),
+        requires: &[],
+    },
+    Target {
+        group: "mac_command",
+        name: "association_status",
+        path: "mac_command/assoc_status.rs",
+        render: Render::Generated(render_mac_command::render_association_status),
+        requires: &[],
+    },
+    Target {
+        group: "mac_command",
+        name: "disassociation_reason",
+        path: "mac_command/disassoc_reason.rs",
+        render: Render::Generated(render_mac_command::render_disassociation_reason),
+        requires: &[],
+    },
+    Target {
+        group: "mac_command",
+        name: "gts_characteristics",
+        path: "mac_command/gts_characteristics.rs",
+        render: Render::Generated(render_mac_command::render_gts_characteristics),
+        requires: &[],
+    },
+    Target {
+        group: "mac_command",
+        name: "commands",
+        path: "mac_command/commands.rs",
+        render: Render::Generated(render_mac_command::render_commands),
+        requires: &[],
+    },
+    Target {
+        group: "mac_command",
+        name: "command_frame",
+        path: "mac_command/frame.rs",
+        render: Render::Rendered(render_mac_command::render_command_frame),
+        requires: &["header", "layout", "parse_error"],
+    },
+    Target {
+        group: "mac_command",
+        name: "enhanced_beacon_request",
+        path: "mac_command/enhanced_beacon_request.rs",
+        render: Render::Source(render_mac_command::render_enhanced_beacon_request),
+        requires: &[
+            "fcs",
+            "header",
+            "frame_ies",
+            "command_frame",
+            "layout",
+            "parse_error",
+        ],
+    },
+];
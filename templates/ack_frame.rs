@@ -0,0 +1,141 @@
+//! The immediate acknowledgement frame, consisting of the frame control field, the
+//! sequence number and the FCS.
+
+use core::convert::TryInto;
+
+use crate::error::{get, ParseError};
+use crate::fcs::{Fcs, FCS_LEN};
+use crate::header::{frame_type, frame_version, FrameControl, FRAME_CONTROL_LEN};
+
+/// The length of an immediate acknowledgement frame including the FCS.
+pub const ACK_FRAME_LEN: usize = FRAME_CONTROL_LEN + 1 + FCS_LEN;
+
+/// A decoded immediate acknowledgement frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AckFrame {
+    pub frame_pending: bool,
+    pub sequence_number: u8,
+}
+
+impl AckFrame {
+    /// Decodes the acknowledgement in `buf`, which includes the FCS. Fails if the frame
+    /// does not have the length of an acknowledgement, its FCS does not match or it is
+    /// not an immediate acknowledgement of frame version 2003 or 2006. Enhanced
+    /// acknowledgements of the current frame version are decoded as a `Frame`.
+    pub fn parse(buf: &[u8]) -> Result<Self, ParseError> {
+        if buf.len() > ACK_FRAME_LEN {
+            return Err(ParseError::InvalidField {
+                offset: ACK_FRAME_LEN,
+            });
+        }
+        let buf = get(buf, 0, ACK_FRAME_LEN)?;
+        if !Fcs::Crc16.verify(buf) {
+            return Err(ParseError::InvalidField {
+                offset: FRAME_CONTROL_LEN + 1,
+            });
+        }
+
+        let frame_control = FrameControl(u16::from_le_bytes(
+            buf[..FRAME_CONTROL_LEN].try_into().unwrap(),
+        ));
+        match (frame_control.frame_type(), frame_control.frame_version()) {
+            (_, version) if version > frame_version::CURRENT => {
+                return Err(ParseError::ReservedFrameVersion { offset: 0 })
+            }
+            (frame_type::ACKNOWLEDGEMENT, frame_version::VERSION_2003)
+            | (frame_type::ACKNOWLEDGEMENT, frame_version::VERSION_2006) => (),
+            _ => return Err(ParseError::UnexpectedFrame { offset: 0 }),
+        }
+
+        Ok(AckFrame {
+            frame_pending: frame_control.frame_pending(),
+            sequence_number: buf[FRAME_CONTROL_LEN],
+        })
+    }
+
+    /// Encodes the acknowledgement including its FCS into `buf`. Returns the length of
+    /// the frame, or `None` if `buf` is too short.
+    pub fn emit(&self, buf: &mut [u8]) -> Option<usize> {
+        let buf = buf.get_mut(..ACK_FRAME_LEN)?;
+
+        let mut frame_control = FrameControl::default();
+        frame_control.set_frame_type(frame_type::ACKNOWLEDGEMENT);
+        frame_control.set_frame_pending(self.frame_pending);
+
+        buf[..FRAME_CONTROL_LEN].copy_from_slice(&frame_control.0.to_le_bytes());
+        buf[FRAME_CONTROL_LEN] = self.sequence_number;
+        Fcs::Crc16.append(buf, FRAME_CONTROL_LEN + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the acknowledgement of the FCS example of IEEE 802.15.4-2006
+    const FRAME: [u8; ACK_FRAME_LEN] = [0x02, 0x00, 0x6a, 0xe4, 0x79];
+
+    #[test]
+    fn parse_and_emit() {
+        let ack = AckFrame {
+            frame_pending: false,
+            sequence_number: 0x6a,
+        };
+        assert_eq!(AckFrame::parse(&FRAME), Ok(ack));
+
+        let mut buf = [0; ACK_FRAME_LEN];
+        assert_eq!(ack.emit(&mut buf), Some(ACK_FRAME_LEN));
+        assert_eq!(buf, FRAME);
+
+        let pending = AckFrame {
+            frame_pending: true,
+            ..ack
+        };
+        pending.emit(&mut buf).unwrap();
+        assert_eq!(AckFrame::parse(&buf), Ok(pending));
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            AckFrame::parse(&FRAME[..4]),
+            Err(ParseError::Truncated { offset: 0 })
+        );
+        assert_eq!(
+            AckFrame::parse(&[0x02, 0x00, 0x6a, 0xe4, 0x79, 0x00]),
+            Err(ParseError::InvalidField {
+                offset: ACK_FRAME_LEN
+            })
+        );
+        assert_eq!(
+            AckFrame::parse(&[0x02, 0x00, 0x6b, 0xe4, 0x79]),
+            Err(ParseError::InvalidField { offset: 3 })
+        );
+    }
+
+    #[test]
+    fn unexpected_frame() {
+        let mut buf = FRAME;
+        let mut parse = |frame_control: u16| {
+            buf[..2].copy_from_slice(&frame_control.to_le_bytes());
+            Fcs::Crc16.append(&mut buf, 3).unwrap();
+            AckFrame::parse(&buf)
+        };
+
+        // a data frame
+        assert_eq!(
+            parse(0x0001),
+            Err(ParseError::UnexpectedFrame { offset: 0 })
+        );
+        // an enhanced acknowledgement
+        assert_eq!(
+            parse(0x2002),
+            Err(ParseError::UnexpectedFrame { offset: 0 })
+        );
+        assert_eq!(
+            parse(0x3002),
+            Err(ParseError::ReservedFrameVersion { offset: 0 })
+        );
+        assert!(parse(0x1002).is_ok());
+    }
+}
@@ -0,0 +1,186 @@
+//! The Enhanced Beacon, a beacon frame of the current frame version whose content is
+//! carried in IEs.
+
+use crate::error::ParseError;
+use crate::fcs::FCS_LEN;
+use crate::header::{frame_type, frame_version, Header};
+use crate::ie::{FrameIes, HeaderIeList};
+use crate::security::AuxiliarySecurityHeader;
+
+/// A decoded Enhanced Beacon borrowing its IEs from the received buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnhancedBeacon<'a> {
+    pub header: Header,
+    /// The auxiliary security header, present if security is enabled in the frame
+    /// control field.
+    pub auxiliary_security_header: Option<AuxiliarySecurityHeader>,
+    /// The IEs describing the network, such as the TSCH IEs, and the beacon payload.
+    /// Only the header IEs of a secured beacon are decoded, the rest is left in
+    /// `secured_payload`.
+    pub ies: FrameIes<'a>,
+    /// The payload IEs and beacon payload of a secured beacon, possibly encrypted,
+    /// which are decoded once the frame has been unsecured. Empty for unsecured
+    /// beacons.
+    pub secured_payload: &'a [u8],
+    /// The MIC following the secured payload, empty for unsecured beacons.
+    pub mic: &'a [u8],
+}
+
+impl<'a> EnhancedBeacon<'a> {
+    /// Decodes the Enhanced Beacon in `buf`, which includes the FCS. Fails if the frame
+    /// is not a beacon of the current frame version or is malformed.
+    pub fn parse(buf: &'a [u8]) -> Result<Self, ParseError> {
+        let (header, mut offset) = Header::parse(buf)?;
+        let frame_control = header.frame_control;
+        if frame_control.frame_type() != frame_type::BEACON
+            || frame_control.frame_version() != frame_version::CURRENT
+        {
+            return Err(ParseError::UnexpectedFrame { offset: 0 });
+        }
+
+        let body_end = buf
+            .len()
+            .checked_sub(FCS_LEN)
+            .filter(|&end| end >= offset)
+            .ok_or(ParseError::Truncated { offset })?;
+
+        if !frame_control.security_enabled() {
+            let ies = FrameIes::parse(&buf[offset..body_end], frame_control.ie_present())
+                .map_err(|e| e.at(offset))?;
+
+            return Ok(EnhancedBeacon {
+                header,
+                auxiliary_security_header: None,
+                ies,
+                secured_payload: &buf[body_end..body_end],
+                mic: &buf[body_end..body_end],
+            });
+        }
+
+        let (aux, len) =
+            AuxiliarySecurityHeader::parse(&buf[offset..body_end], frame_version::CURRENT)
+                .map_err(|e| e.at(offset))?;
+        offset += len;
+        let mic_start = body_end
+            .checked_sub(aux.security_control.mic_len())
+            .filter(|&start| start >= offset)
+            .ok_or(ParseError::Truncated { offset })?;
+
+        // only the header IEs are sent in the open
+        let header_ies = if frame_control.ie_present() {
+            let ies = HeaderIeList::parse(&buf[offset..mic_start]).map_err(|e| e.at(offset))?;
+            Some(ies)
+        } else {
+            None
+        };
+        let payload_offset = offset + header_ies.map_or(0, |ies| ies.buffer_len());
+
+        Ok(EnhancedBeacon {
+            header,
+            auxiliary_security_header: Some(aux),
+            ies: FrameIes {
+                header_ies,
+                payload_ies: None,
+                payload: &buf[mic_start..mic_start],
+            },
+            secured_payload: &buf[payload_offset..mic_start],
+            mic: &buf[mic_start..body_end],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::Address;
+    use crate::ie::{HeaderIe, NestedIe};
+
+    #[test]
+    fn tsch_ies() {
+        // a beacon with a Header Termination 1 IE and an MLME IE holding the TSCH
+        // Synchronization IE, followed by the FCS
+        let buf = [
+            0x00, 0xa2, 0x01, 0xcd, 0xab, 0x34, 0x12, 0x00, 0x3f, 0x08, 0x88, 0x06, 0x1a, 0x05,
+            0x04, 0x03, 0x02, 0x01, 0x07, 0x00, 0x00,
+        ];
+
+        let beacon = EnhancedBeacon::parse(&buf).unwrap();
+        assert_eq!(beacon.header.sequence_number, Some(1));
+        assert_eq!(beacon.header.src_pan, Some(0xabcd));
+        assert_eq!(beacon.header.src_address, Address::Short(0x1234));
+        assert_eq!(beacon.auxiliary_security_header, None);
+        assert_eq!(beacon.ies.header_ies.unwrap().iter().count(), 0);
+        assert_eq!(beacon.ies.payload, &[]);
+        assert_eq!(beacon.secured_payload, &[]);
+        assert_eq!(beacon.mic, &[]);
+
+        let mut mlme_ies = beacon.ies.mlme_ies();
+        let (offset, ie) = mlme_ies.next().unwrap().unwrap();
+        assert_eq!(offset, 4);
+        assert_eq!(
+            ie,
+            NestedIe::Short {
+                sub_id: 0x1a,
+                content: &[0x05, 0x04, 0x03, 0x02, 0x01, 0x07],
+            }
+        );
+        assert!(mlme_ies.next().is_none());
+    }
+
+    #[test]
+    fn secured() {
+        // a beacon secured using ENC-MIC-32 and a key index, with a Global Time IE and
+        // a Header Termination 1 IE in the open, followed by the encrypted payload IEs,
+        // the MIC and the FCS
+        let buf = [
+            0x08, 0xa2, 0x01, 0xcd, 0xab, 0x34, 0x12, 0x0d, 0x05, 0x00, 0x00, 0x00, 0x01, 0x84,
+            0x14, 0x00, 0x5e, 0xd0, 0xb2, 0x00, 0x3f, 0xe1, 0xe2, 0xe3, 0xa1, 0xa2, 0xa3, 0xa4,
+            0x00, 0x00,
+        ];
+
+        let beacon = EnhancedBeacon::parse(&buf).unwrap();
+        let aux = beacon.auxiliary_security_header.unwrap();
+        assert_eq!(aux.frame_counter, Some(5));
+        assert_eq!(aux.security_control.mic_len(), 4);
+
+        let mut header_ies = beacon.ies.header_ies.unwrap().iter();
+        assert_eq!(
+            header_ies.next(),
+            Some(HeaderIe {
+                element_id: 0x29,
+                content: &[0x00, 0x5e, 0xd0, 0xb2],
+            })
+        );
+        assert_eq!(header_ies.next(), None);
+        assert_eq!(beacon.ies.payload_ies, None);
+        assert_eq!(beacon.secured_payload, &[0xe1, 0xe2, 0xe3]);
+        assert_eq!(beacon.mic, &[0xa1, 0xa2, 0xa3, 0xa4]);
+
+        // the header IEs cannot overlap the MIC
+        assert_eq!(
+            EnhancedBeacon::parse(&buf[..21]),
+            Err(ParseError::IeOverrun { offset: 13 })
+        );
+        assert_eq!(
+            EnhancedBeacon::parse(&buf[..16]),
+            Err(ParseError::Truncated { offset: 13 })
+        );
+    }
+
+    #[test]
+    fn unexpected_frame() {
+        // the beacon of frame version 2006
+        let buf = [0x00, 0x90, 0x01, 0xcd, 0xab, 0x34, 0x12, 0x00, 0x00];
+        assert_eq!(
+            EnhancedBeacon::parse(&buf),
+            Err(ParseError::UnexpectedFrame { offset: 0 })
+        );
+
+        // a data frame of the current frame version
+        let buf = [0x01, 0xa2, 0x01, 0xcd, 0xab, 0x34, 0x12, 0x00, 0x00];
+        assert_eq!(
+            EnhancedBeacon::parse(&buf),
+            Err(ParseError::UnexpectedFrame { offset: 0 })
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! The beacon payload, assembled from the superframe specification, the GTS fields and
+//! the pending address fields.
+
+use core::convert::TryInto;
+
+use crate::error::{get, ParseError};
+
+const SUPERFRAME_LEN: usize = 2;
+const GTS_DESCRIPTOR_LEN: usize = 3;
+const SHORT_ADDRESS_LEN: usize = 2;
+const EXTENDED_ADDRESS_LEN: usize = 8;
+
+/// A guaranteed time slot described by one of the GTS descriptors of a beacon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GtsAllocation {
+    pub short_address: u16,
+    pub starting_slot: u8,
+    pub length: u8,
+}
+
+/// A decoded beacon payload borrowing the variable length lists from the received
+/// buffer. Bitfield valued fields are kept as their raw encoding, which can be
+/// interpreted using `Superframe`, `GTS_specification`, `GTS_Directions` and
+/// `Pending_address_specification`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BeaconPayload<'a> {
+    pub superframe: u16,
+    pub gts_specification: u8,
+    /// Only present if the GTS specification contains at least one descriptor.
+    pub gts_directions: Option<u8>,
+    pub pending_address_specification: u8,
+    gts_descriptors: &'a [u8],
+    short_addresses: &'a [u8],
+    extended_addresses: &'a [u8],
+    /// The beacon payload supplied by the higher layer.
+    pub payload: &'a [u8],
+}
+
+impl<'a> BeaconPayload<'a> {
+    /// Decodes the MAC payload of a beacon frame. Fails if `buf` is shorter than the
+    /// lists announced by the GTS and pending address specifications.
+    pub fn parse(buf: &'a [u8]) -> Result<Self, ParseError> {
+        let superframe = u16::from_le_bytes(get(buf, 0, SUPERFRAME_LEN)?.try_into().unwrap());
+        let mut offset = SUPERFRAME_LEN;
+
+        let mut take = |len: usize| -> Result<&'a [u8], ParseError> {
+            let taken = get(buf, offset, offset + len)?;
+            offset += len;
+            Ok(taken)
+        };
+
+        let gts_specification = take(1)?[0];
+        let descriptor_count = usize::from(gts_specification & 0b111);
+
+        let gts_directions = if descriptor_count > 0 {
+            Some(take(1)?[0])
+        } else {
+            None
+        };
+        let gts_descriptors = take(descriptor_count * GTS_DESCRIPTOR_LEN)?;
+
+        let pending_address_specification = take(1)?[0];
+        let number_short_addresses = usize::from(pending_address_specification & 0b111);
+        let number_extended_addresses = usize::from((pending_address_specification >> 4) & 0b111);
+
+        let short_addresses = take(number_short_addresses * SHORT_ADDRESS_LEN)?;
+        let extended_addresses = take(number_extended_addresses * EXTENDED_ADDRESS_LEN)?;
+
+        Ok(BeaconPayload {
+            superframe,
+            gts_specification,
+            gts_directions,
+            pending_address_specification,
+            gts_descriptors,
+            short_addresses,
+            extended_addresses,
+            payload: &buf[offset..],
+        })
+    }
+
+    /// The guaranteed time slots listed in the GTS descriptors.
+    pub fn gts_descriptors(&self) -> impl Iterator<Item = GtsAllocation> + 'a {
+        self.gts_descriptors
+            .chunks_exact(GTS_DESCRIPTOR_LEN)
+            .map(|d| GtsAllocation {
+                short_address: u16::from_le_bytes([d[0], d[1]]),
+                starting_slot: d[2] & 0x0f,
+                length: d[2] >> 4,
+            })
+    }
+
+    /// The short addresses of the devices the coordinator has pending data for.
+    pub fn pending_short_addresses(&self) -> impl Iterator<Item = u16> + 'a {
+        self.short_addresses
+            .chunks_exact(SHORT_ADDRESS_LEN)
+            .map(|a| u16::from_le_bytes([a[0], a[1]]))
+    }
+
+    /// The extended addresses of the devices the coordinator has pending data for.
+    pub fn pending_extended_addresses(&self) -> impl Iterator<Item = u64> + 'a {
+        self.extended_addresses
+            .chunks_exact(EXTENDED_ADDRESS_LEN)
+            .map(|a| u64::from_le_bytes(a.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a beacon of a PAN coordinator permitting association, with 2 GTSs, data pending
+    // for a short and an extended address, and 1 octet of beacon payload
+    const BEACON: [u8; 22] = [
+        0xff, 0xcf, // superframe specification
+        0x82, 0x01, // GTS specification and directions
+        0x34, 0x12, 0x29, 0x78, 0x56, 0x4b, // GTS descriptors
+        0x11, // pending address specification
+        0xcd, 0xab, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, // pending addresses
+        0x42,
+    ];
+
+    #[test]
+    fn parse() {
+        let payload = BeaconPayload::parse(&BEACON).unwrap();
+
+        assert_eq!(payload.superframe, 0xcfff);
+        assert_eq!(payload.gts_specification, 0x82);
+        assert_eq!(payload.gts_directions, Some(0x01));
+        assert!(payload.gts_descriptors().eq([
+            GtsAllocation {
+                short_address: 0x1234,
+                starting_slot: 9,
+                length: 2,
+            },
+            GtsAllocation {
+                short_address: 0x5678,
+                starting_slot: 11,
+                length: 4,
+            },
+        ]
+        .iter()
+        .cloned()));
+        assert_eq!(payload.pending_address_specification, 0x11);
+        assert!(payload
+            .pending_short_addresses()
+            .eq([0xabcd].iter().cloned()));
+        assert!(payload
+            .pending_extended_addresses()
+            .eq([0xacde_4800_0000_0001].iter().cloned()));
+        assert_eq!(payload.payload, &[0x42]);
+    }
+
+    #[test]
+    fn without_gts() {
+        // the GTS directions are omitted without GTS descriptors
+        let payload = BeaconPayload::parse(&[0xff, 0xcf, 0x80, 0x00]).unwrap();
+
+        assert_eq!(payload.gts_directions, None);
+        assert_eq!(payload.gts_descriptors().count(), 0);
+        assert_eq!(payload.pending_short_addresses().count(), 0);
+        assert_eq!(payload.payload, &[]);
+    }
+
+    #[test]
+    fn truncated() {
+        assert_eq!(
+            BeaconPayload::parse(&BEACON[..20]),
+            Err(ParseError::Truncated { offset: 13 })
+        );
+        assert_eq!(
+            BeaconPayload::parse(&BEACON[..8]),
+            Err(ParseError::Truncated { offset: 4 })
+        );
+        assert_eq!(
+            BeaconPayload::parse(&BEACON[..1]),
+            Err(ParseError::Truncated { offset: 0 })
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Building frames into a buffer, deriving the frame control field from the fields which
+//! are set.
+
+use crate::header::{frame_type, frame_version, Address, FrameControl, Header};
+use crate::ie::{
+    group_ids, header_ie_descriptor, payload_ie_descriptor, HEADER_TERMINATION_1,
+    HEADER_TERMINATION_2, IE_DESCRIPTOR_LEN,
+};
+use crate::security::AuxiliarySecurityHeader;
+
+/// Builds a frame from its addressing fields, auxiliary security header, IEs and payload.
+///
+/// The frame type, frame version, security enabled, IE present, address modes, PAN ID
+/// compression and sequence number suppression fields of the frame control field are
+/// derived from the fields which are set. Unless set explicitly, frame version 2006 is
+/// used if it can express the frame and the current frame version otherwise.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameBuilder<'a> {
+    frame_type: u8,
+    frame_version: Option<u8>,
+    command_frame_id: Option<u8>,
+    frame_pending: bool,
+    ack_request: bool,
+    sequence_number: Option<u8>,
+    dst: Option<(u16, Address)>,
+    src_pan: Option<u16>,
+    src_address: Address,
+    security: Option<AuxiliarySecurityHeader>,
+    header_ies: &'a [u8],
+    payload_ies: &'a [u8],
+    payload: &'a [u8],
+}
+
+impl<'a> FrameBuilder<'a> {
+    fn new(frame_type: u8) -> Self {
+        FrameBuilder {
+            frame_type,
+            frame_version: None,
+            command_frame_id: None,
+            frame_pending: false,
+            ack_request: false,
+            sequence_number: None,
+            dst: None,
+            src_pan: None,
+            src_address: Address::None,
+            security: None,
+            header_ies: &[],
+            payload_ies: &[],
+            payload: &[],
+        }
+    }
+
+    pub fn beacon() -> Self {
+        Self::new(frame_type::BEACON)
+    }
+
+    pub fn data() -> Self {
+        Self::new(frame_type::DATA)
+    }
+
+    /// A MAC command frame, whose payload starts with `command_frame_id`.
+    pub fn mac_command(command_frame_id: u8) -> Self {
+        FrameBuilder {
+            command_frame_id: Some(command_frame_id),
+            ..Self::new(frame_type::MAC_COMMAND)
+        }
+    }
+
+    /// Uses `version` instead of deriving the frame version.
+    pub fn frame_version(self, version: u8) -> Self {
+        FrameBuilder {
+            frame_version: Some(version),
+            ..self
+        }
+    }
+
+    /// Sets the sequence number, which is suppressed otherwise.
+    pub fn sequence_number(self, sequence_number: u8) -> Self {
+        FrameBuilder {
+            sequence_number: Some(sequence_number),
+            ..self
+        }
+    }
+
+    pub fn frame_pending(self, frame_pending: bool) -> Self {
+        FrameBuilder {
+            frame_pending,
+            ..self
+        }
+    }
+
+    pub fn ack_request(self, ack_request: bool) -> Self {
+        FrameBuilder {
+            ack_request,
+            ..self
+        }
+    }
+
+    pub fn dst_short(self, pan_id: u16, address: u16) -> Self {
+        FrameBuilder {
+            dst: Some((pan_id, Address::Short(address))),
+            ..self
+        }
+    }
+
+    pub fn dst_ext(self, pan_id: u16, address: u64) -> Self {
+        FrameBuilder {
+            dst: Some((pan_id, Address::Extended(address))),
+            ..self
+        }
+    }
+
+    /// Sets the short source address. The source is in the PAN of the destination
+    /// unless set by `src_pan`.
+    pub fn src_short(self, address: u16) -> Self {
+        FrameBuilder {
+            src_address: Address::Short(address),
+            ..self
+        }
+    }
+
+    /// Sets the extended source address. The source is in the PAN of the destination
+    /// unless set by `src_pan`.
+    pub fn src_ext(self, address: u64) -> Self {
+        FrameBuilder {
+            src_address: Address::Extended(address),
+            ..self
+        }
+    }
+
+    /// Sets the PAN of the source, which is required for frames without a destination.
+    pub fn src_pan(self, pan_id: u16) -> Self {
+        FrameBuilder {
+            src_pan: Some(pan_id),
+            ..self
+        }
+    }
+
+    /// Includes the auxiliary security header `aux`. The frame built is unsecured and
+    /// must be secured by `secure_frame` before the FCS is appended. Frames built without
+    /// an auxiliary security header may instead be secured by `secure_outgoing`.
+    pub fn secure(self, aux: AuxiliarySecurityHeader) -> Self {
+        FrameBuilder {
+            security: Some(aux),
+            ..self
+        }
+    }
+
+    /// Sets the encoded header IEs and payload IEs, excluding the termination IEs, which
+    /// are inserted as required.
+    pub fn ies(self, header_ies: &'a [u8], payload_ies: &'a [u8]) -> Self {
+        FrameBuilder {
+            header_ies,
+            payload_ies,
+            ..self
+        }
+    }
+
+    pub fn payload(self, payload: &'a [u8]) -> Self {
+        FrameBuilder { payload, ..self }
+    }
+
+    /// The frame version used by the frame.
+    pub fn version(&self) -> u8 {
+        self.frame_version.unwrap_or_else(|| {
+            let security_2015 = self.security.map_or(false, |aux| {
+                aux.frame_counter.is_none() || aux.security_control.asn_in_nonce()
+            });
+
+            if self.sequence_number.is_none()
+                || !self.header_ies.is_empty()
+                || !self.payload_ies.is_empty()
+                || security_2015
+            {
+                frame_version::CURRENT
+            } else {
+                frame_version::VERSION_2006
+            }
+        })
+    }
+
+    fn header(&self) -> Header {
+        let mut frame_control = FrameControl(0);
+        frame_control.set_frame_type(self.frame_type);
+        frame_control.set_frame_version(self.version());
+        frame_control.set_frame_pending(self.frame_pending);
+        frame_control.set_ack_request(self.ack_request);
+        frame_control.set_security_enabled(self.security.is_some());
+        frame_control.set_ie_present(!self.header_ies.is_empty() || !self.payload_ies.is_empty());
+
+        let dst_pan = self.dst.map(|(pan_id, _)| pan_id);
+        let src_pan = match (self.src_address, dst_pan) {
+            (Address::None, _) => None,
+            (_, Some(dst_pan)) if self.src_pan == Some(dst_pan) => None,
+            _ => self.src_pan,
+        };
+
+        Header {
+            frame_control,
+            sequence_number: self.sequence_number,
+            dst_pan,
+            dst_address: self.dst.map_or(Address::None, |(_, address)| address),
+            src_pan,
+            src_address: self.src_address,
+        }
+    }
+
+    /// Encodes the frame into `buf`, excluding the FCS. Returns the length of the frame,
+    /// or `None` if `buf` is too short or the frame version cannot express the frame.
+    pub fn build(&self, buf: &mut [u8]) -> Option<usize> {
+        let version = self.version();
+        let mut offset = self.header().emit(buf)?;
+
+        if let Some(aux) = self.security {
+            offset += aux.emit(buf.get_mut(offset..)?, version)?;
+        }
+
+        let command_frame_id = self
+            .command_frame_id
+            .as_ref()
+            .map_or(&[][..], core::slice::from_ref);
+        let payload_present = !command_frame_id.is_empty() || !self.payload.is_empty();
+
+        let mut write = |data: &[u8]| -> Option<()> {
+            buf.get_mut(offset..offset + data.len())?
+                .copy_from_slice(data);
+            offset += data.len();
+            Some(())
+        };
+
+        write(self.header_ies)?;
+        if !self.payload_ies.is_empty() {
+            write(&header_ie_descriptor(HEADER_TERMINATION_1, 0)?)?;
+            write(self.payload_ies)?;
+            if payload_present {
+                write(&payload_ie_descriptor(group_ids::PAYLOAD_TERMINATION, 0)?)?;
+            }
+        } else if !self.header_ies.is_empty() && payload_present {
+            write(&header_ie_descriptor(HEADER_TERMINATION_2, 0)?)?;
+        }
+
+        write(command_frame_id)?;
+        write(self.payload)?;
+
+        Some(offset)
+    }
+
+    /// The length of the encoded frame, excluding the FCS and MIC.
+    pub fn buffer_len(&self) -> usize {
+        let payload_present = self.command_frame_id.is_some() || !self.payload.is_empty();
+        let termination_len = if !self.payload_ies.is_empty() {
+            IE_DESCRIPTOR_LEN * (1 + payload_present as usize)
+        } else if !self.header_ies.is_empty() && payload_present {
+            IE_DESCRIPTOR_LEN
+        } else {
+            0
+        };
+
+        self.header().buffer_len()
+            + self.security.map_or(0, |aux| aux.buffer_len())
+            + self.header_ies.len()
+            + termination_len
+            + self.payload_ies.len()
+            + self.command_frame_id.map_or(0, |_| 1)
+            + self.payload.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{KeyIdentifier, SecurityControl};
+
+    #[test]
+    fn annex_c_data_frame() {
+        let aux = AuxiliarySecurityHeader {
+            security_control: SecurityControl(0b100),
+            frame_counter: Some(5),
+            key_identifier: KeyIdentifier::None,
+        };
+        let builder = FrameBuilder::data()
+            .sequence_number(0x84)
+            .ack_request(true)
+            .dst_ext(0x4321, 0xacde_4800_0000_0002)
+            .src_ext(0xacde_4800_0000_0001)
+            .secure(aux)
+            .payload(b"abcd");
+
+        let mut buf = [0; 32];
+        let len = builder.build(&mut buf).unwrap();
+
+        assert_eq!(len, builder.buffer_len());
+        assert_eq!(
+            &buf[..len],
+            &[
+                0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01,
+                0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x04, 0x05, 0x00, 0x00, 0x00, 0x61, 0x62,
+                0x63, 0x64
+            ][..]
+        );
+    }
+
+    #[test]
+    fn ie_terminations() {
+        let header_ies = [0x00, 0x0f];
+        let payload_ies = [0x00, 0x88];
+        let builder = FrameBuilder::mac_command(0x07)
+            .dst_short(0xabcd, 0xffff)
+            .ies(&header_ies, &payload_ies);
+
+        let mut buf = [0; 32];
+        let len = builder.build(&mut buf).unwrap();
+
+        assert_eq!(len, builder.buffer_len());
+        let frame_control = FrameControl(u16::from_le_bytes([buf[0], buf[1]]));
+        assert_eq!(frame_control.frame_version(), frame_version::CURRENT);
+        assert!(frame_control.ie_present());
+        assert!(frame_control.seq_nr_suppressed());
+        assert_eq!(
+            &buf[6..len],
+            &[0x00, 0x0f, 0x00, 0x3f, 0x00, 0x88, 0x00, 0xf8, 0x07][..]
+        );
+    }
+}
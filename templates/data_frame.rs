@@ -0,0 +1,181 @@
+//! The data frame, consisting of the MHR, an optional auxiliary security header, the
+//! payload and the FCS.
+
+use crate::error::ParseError;
+use crate::fcs::{Fcs, FCS_LEN};
+use crate::header::{frame_type, Header};
+use crate::security::AuxiliarySecurityHeader;
+
+/// A decoded data frame borrowing its contents from the received buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DataFrame<'a> {
+    pub header: Header,
+    /// The auxiliary security header, present if security is enabled in the frame
+    /// control field.
+    pub auxiliary_security_header: Option<AuxiliarySecurityHeader>,
+    /// The payload, encrypted if the security level requires it.
+    pub payload: &'a [u8],
+    /// The MIC following the payload, empty for unsecured frames.
+    pub mic: &'a [u8],
+    pub fcs: &'a [u8],
+}
+
+impl<'a> DataFrame<'a> {
+    /// Decodes the data frame in `buf`, which includes the FCS. Fails if the frame is
+    /// not a data frame, is truncated or is secured using frame version 2003.
+    pub fn parse(buf: &'a [u8]) -> Result<Self, ParseError> {
+        let (header, mut offset) = Header::parse(buf)?;
+        if header.frame_control.frame_type() != frame_type::DATA {
+            return Err(ParseError::UnexpectedFrame { offset: 0 });
+        }
+
+        let body_end = buf
+            .len()
+            .checked_sub(FCS_LEN)
+            .filter(|&end| end >= offset)
+            .ok_or(ParseError::Truncated { offset })?;
+
+        let auxiliary_security_header = if header.frame_control.security_enabled() {
+            let (aux, len) = AuxiliarySecurityHeader::parse(
+                &buf[offset..body_end],
+                header.frame_control.frame_version(),
+            )
+            .map_err(|e| e.at(offset))?;
+            offset += len;
+            Some(aux)
+        } else {
+            None
+        };
+
+        let mic_len = auxiliary_security_header.map_or(0, |aux| aux.security_control.mic_len());
+        let payload_end = body_end
+            .checked_sub(mic_len)
+            .filter(|&end| end >= offset)
+            .ok_or(ParseError::Truncated { offset })?;
+
+        Ok(DataFrame {
+            header,
+            auxiliary_security_header,
+            payload: &buf[offset..payload_end],
+            mic: &buf[payload_end..body_end],
+            fcs: &buf[body_end..],
+        })
+    }
+
+    /// The length of the encoded frame including the FCS.
+    pub fn buffer_len(&self) -> usize {
+        self.header.buffer_len()
+            + self
+                .auxiliary_security_header
+                .map_or(0, |aux| aux.buffer_len())
+            + self.payload.len()
+            + self.mic.len()
+            + FCS_LEN
+    }
+
+    /// Encodes the frame into `buf`, setting the frame type and security enabled bits
+    /// of the frame control field, and appends the 2-octet FCS. Returns the length of
+    /// the frame, or `None` if `buf` is too short or the frame version cannot express
+    /// the auxiliary security header.
+    pub fn emit(&self, buf: &mut [u8]) -> Option<usize> {
+        let len = self.buffer_len();
+        let buf = buf.get_mut(..len)?;
+
+        let mut header = self.header;
+        header.frame_control.set_frame_type(frame_type::DATA);
+        header
+            .frame_control
+            .set_security_enabled(self.auxiliary_security_header.is_some());
+        let mut offset = header.emit(buf)?;
+
+        if let Some(aux) = self.auxiliary_security_header {
+            offset += aux.emit(&mut buf[offset..], header.frame_control.frame_version())?;
+        }
+        for field in &[self.payload, self.mic] {
+            buf[offset..offset + field.len()].copy_from_slice(field);
+            offset += field.len();
+        }
+
+        Fcs::Crc16.append(buf, offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::Address;
+
+    // the secured data frame of Annex C of IEEE 802.15.4-2006
+    const FRAME: [u8; 30] = [
+        0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x04, 0x05, 0x00, 0x00, 0x00, 0xd4, 0x3e, 0x02, 0x2b,
+    ];
+
+    #[test]
+    fn parse_and_emit() {
+        let mut buf = [0; 32];
+        buf[..30].copy_from_slice(&FRAME);
+        Fcs::Crc16.append(&mut buf, 30).unwrap();
+
+        let frame = DataFrame::parse(&buf).unwrap();
+        assert_eq!(frame.header.sequence_number, Some(0x84));
+        assert_eq!(frame.header.dst_pan, Some(0x4321));
+        assert_eq!(
+            frame.header.src_address,
+            Address::Extended(0xacde_4800_0000_0001)
+        );
+        let aux = frame.auxiliary_security_header.unwrap();
+        assert_eq!(aux.security_control.security_level(), 4);
+        assert_eq!(aux.frame_counter, Some(5));
+        // the encrypted payload, security level 4 does not append a MIC
+        assert_eq!(frame.payload, &FRAME[26..]);
+        assert_eq!(frame.mic, &[]);
+        assert_eq!(frame.buffer_len(), buf.len());
+
+        let mut emitted = [0; 32];
+        assert_eq!(frame.emit(&mut emitted), Some(32));
+        assert_eq!(emitted, buf);
+    }
+
+    #[test]
+    fn truncated() {
+        // the payload is cut short of the FCS
+        assert_eq!(
+            DataFrame::parse(&FRAME[..25]),
+            Err(ParseError::Truncated { offset: 22 })
+        );
+    }
+
+    #[test]
+    fn mic() {
+        // the frame of Annex C using MIC-32, with the payload and the MIC replaced
+        let mut buf = [0; 38];
+        buf[..30].copy_from_slice(&FRAME);
+        buf[21] = 0x05;
+        buf[30..34].copy_from_slice(&[0xa1, 0xa2, 0xa3, 0xa4]);
+        Fcs::Crc16.append(&mut buf, 34).unwrap();
+
+        let frame = DataFrame::parse(&buf[..36]).unwrap();
+        assert_eq!(frame.payload, &FRAME[26..]);
+        assert_eq!(frame.mic, &[0xa1, 0xa2, 0xa3, 0xa4]);
+
+        let mut emitted = [0; 36];
+        assert_eq!(frame.emit(&mut emitted), Some(36));
+        assert_eq!(&emitted[..], &buf[..36]);
+
+        // the MIC is cut short
+        assert_eq!(
+            DataFrame::parse(&buf[..30]),
+            Err(ParseError::Truncated { offset: 26 })
+        );
+    }
+
+    #[test]
+    fn unexpected_frame() {
+        // an acknowledgement
+        assert_eq!(
+            DataFrame::parse(&[0x02, 0x00, 0x6a, 0xe4, 0x79]),
+            Err(ParseError::UnexpectedFrame { offset: 0 })
+        );
+    }
+}
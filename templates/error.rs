@@ -0,0 +1,105 @@
+//! The errors reported when decoding malformed frames.
+
+use core::fmt;
+
+/// The reason a frame or one of its fields could not be decoded, with the offset of the
+/// offending field relative to the start of the buffer passed to the decoder.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The buffer ends before the field starting at `offset` is complete.
+    Truncated { offset: usize },
+    /// The frame control field at `offset` uses the reserved address mode 0b01.
+    ReservedAddressMode { offset: usize },
+    /// The frame control field at `offset` uses the reserved frame version 0b11.
+    ReservedFrameVersion { offset: usize },
+    /// The security control field at `offset` uses a reserved security level.
+    ReservedSecurityLevel { offset: usize },
+    /// The frame at `offset` is secured using frame version 2003, which is not
+    /// supported.
+    UnsupportedSecurity { offset: usize },
+    /// The content of the IE whose descriptor is at `offset` extends beyond the frame.
+    IeOverrun { offset: usize },
+    /// The IE at `offset` is of the wrong kind, such as a header IE in the payload IE
+    /// list.
+    UnexpectedIe { offset: usize },
+    /// The frame control field at `offset` does not match the decoded frame, such as
+    /// the frame type or frame version.
+    UnexpectedFrame { offset: usize },
+    /// The field at `offset` has a value or length which does not match its layout.
+    InvalidField { offset: usize },
+}
+
+impl ParseError {
+    /// The offset of the offending field.
+    pub fn offset(&self) -> usize {
+        match *self {
+            ParseError::Truncated { offset }
+            | ParseError::ReservedAddressMode { offset }
+            | ParseError::ReservedFrameVersion { offset }
+            | ParseError::ReservedSecurityLevel { offset }
+            | ParseError::UnsupportedSecurity { offset }
+            | ParseError::IeOverrun { offset }
+            | ParseError::UnexpectedIe { offset }
+            | ParseError::UnexpectedFrame { offset }
+            | ParseError::InvalidField { offset } => offset,
+        }
+    }
+
+    /// Moves the error by `base`, for errors of a decoder applied to the part of a
+    /// buffer starting at `base`.
+    pub fn at(self, base: usize) -> Self {
+        let offset = self.offset() + base;
+
+        match self {
+            ParseError::Truncated { .. } => ParseError::Truncated { offset },
+            ParseError::ReservedAddressMode { .. } => ParseError::ReservedAddressMode { offset },
+            ParseError::ReservedFrameVersion { .. } => ParseError::ReservedFrameVersion { offset },
+            ParseError::ReservedSecurityLevel { .. } => {
+                ParseError::ReservedSecurityLevel { offset }
+            }
+            ParseError::UnsupportedSecurity { .. } => ParseError::UnsupportedSecurity { offset },
+            ParseError::IeOverrun { .. } => ParseError::IeOverrun { offset },
+            ParseError::UnexpectedIe { .. } => ParseError::UnexpectedIe { offset },
+            ParseError::UnexpectedFrame { .. } => ParseError::UnexpectedFrame { offset },
+            ParseError::InvalidField { .. } => ParseError::InvalidField { offset },
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            ParseError::Truncated { .. } => "truncated field",
+            ParseError::ReservedAddressMode { .. } => "reserved address mode",
+            ParseError::ReservedFrameVersion { .. } => "reserved frame version",
+            ParseError::ReservedSecurityLevel { .. } => "reserved security level",
+            ParseError::UnsupportedSecurity { .. } => "unsupported frame version 2003 security",
+            ParseError::IeOverrun { .. } => "IE overruns the frame",
+            ParseError::UnexpectedIe { .. } => "unexpected IE",
+            ParseError::UnexpectedFrame { .. } => "unexpected frame",
+            ParseError::InvalidField { .. } => "invalid field",
+        };
+
+        write!(f, "{} at offset {}", reason, self.offset())
+    }
+}
+
+/// Returns the part of `buf` from `start` to `end`, or a `Truncated` error at `start` if
+/// `buf` is too short.
+pub(crate) fn get(buf: &[u8], start: usize, end: usize) -> Result<&[u8], ParseError> {
+    buf.get(start..end)
+        .ok_or(ParseError::Truncated { offset: start })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn at() {
+        let e = ParseError::IeOverrun { offset: 2 }.at(6);
+
+        assert_eq!(e, ParseError::IeOverrun { offset: 8 });
+        assert_eq!(e.offset(), 8);
+    }
+}
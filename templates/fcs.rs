@@ -0,0 +1,112 @@
+//! Computation of the frame check sequence (FCS).
+
+/// The length of the 2-octet FCS used by most PHYs.
+pub const FCS_LEN: usize = 2;
+
+/// The kind of FCS appended to a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fcs {
+    /// The 2-octet FCS, a CRC-16 using the ITU-T polynomial.
+    Crc16,
+    /// The 4-octet FCS, a CRC-32 as used by the SUN PHYs when configured so.
+    Crc32,
+}
+
+impl Fcs {
+    /// The number of octets of the FCS.
+    pub fn size(self) -> usize {
+        match self {
+            Fcs::Crc16 => 2,
+            Fcs::Crc32 => 4,
+        }
+    }
+
+    /// Computes the FCS over `data`.
+    pub fn compute(self, data: &[u8]) -> u32 {
+        match self {
+            Fcs::Crc16 => u32::from(crc16(data)),
+            Fcs::Crc32 => crc32(data),
+        }
+    }
+
+    /// Checks the FCS at the end of `frame` against the octets preceding it.
+    pub fn verify(self, frame: &[u8]) -> bool {
+        let len = match frame.len().checked_sub(self.size()) {
+            Some(len) => len,
+            None => return false,
+        };
+
+        let fcs = self.compute(&frame[..len]).to_le_bytes();
+        frame[len..] == fcs[..self.size()]
+    }
+
+    /// Computes the FCS over the first `len` octets of `buf` and writes it after them.
+    /// Returns the length of the frame including the FCS, or `None` if `buf` is too
+    /// short.
+    pub fn append(self, buf: &mut [u8], len: usize) -> Option<usize> {
+        let end = len + self.size();
+        let fcs = self.compute(buf.get(..len)?).to_le_bytes();
+        buf.get_mut(len..end)?.copy_from_slice(&fcs[..self.size()]);
+
+        Some(end)
+    }
+}
+
+/// The CRC-16 of the 2-octet FCS, using the polynomial x^16 + x^12 + x^5 + 1 with a
+/// zero initial value, processing each octet least significant bit first.
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &octet in data {
+        crc ^= u16::from(octet);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+/// The CRC-32 of the 4-octet FCS, as specified for IEEE 802.3.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &octet in data {
+        crc ^= u32::from(octet);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_values() {
+        // CRC-16/KERMIT and CRC-32 of the usual check input
+        assert_eq!(crc16(b"123456789"), 0x2189);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn ack_frame() {
+        // the acknowledgement frame of the FCS example of IEEE 802.15.4-2006
+        let frame = [0x02, 0x00, 0x6a, 0xe4, 0x79];
+        assert!(Fcs::Crc16.verify(&frame));
+        assert!(!Fcs::Crc16.verify(&frame[..4]));
+
+        let mut buf = [0; 5];
+        buf[..3].copy_from_slice(&frame[..3]);
+        assert_eq!(Fcs::Crc16.append(&mut buf, 3), Some(5));
+        assert_eq!(buf, frame);
+        assert_eq!(Fcs::Crc32.append(&mut buf, 3), None);
+    }
+}
@@ -0,0 +1,348 @@
+//! A frame stored in a buffer, decoding its fields in place.
+
+use crate::error::ParseError;
+use crate::fcs::Fcs;
+use crate::header::{Address, FrameControl, Header, FRAME_CONTROL_LEN};
+use crate::security::AuxiliarySecurityHeader;
+
+/// The offsets of the fields of the MHR, computed from the frame control field.
+#[derive(Clone, Copy, Debug)]
+struct Offsets {
+    sequence_number: Option<usize>,
+    dst_pan: Option<usize>,
+    dst_addr: usize,
+    src_pan: Option<usize>,
+    src_addr: usize,
+    end: usize,
+}
+
+impl Offsets {
+    /// Returns `None` for a reserved address mode or frame version.
+    fn new(frame_control: &FrameControl) -> Option<Self> {
+        let (dst_pan_present, src_pan_present) = frame_control.pan_ids_present()?;
+        let dst_addr_len = Address::len_of_mode(frame_control.dst_addr_mode())?;
+        let src_addr_len = Address::len_of_mode(frame_control.src_addr_mode())?;
+
+        let mut offset = FRAME_CONTROL_LEN;
+        let mut field = |present: bool, len: usize| {
+            if !present {
+                return None;
+            }
+            offset += len;
+            Some(offset - len)
+        };
+
+        let sequence_number = field(frame_control.sequence_number_present(), 1);
+        let dst_pan = field(dst_pan_present, 2);
+        let dst_addr = field(true, dst_addr_len)?;
+        let src_pan = field(src_pan_present, 2);
+        let src_addr = field(true, src_addr_len)?;
+
+        Some(Offsets {
+            sequence_number,
+            dst_pan,
+            dst_addr,
+            src_pan,
+            src_addr,
+            end: offset,
+        })
+    }
+}
+
+/// A frame stored in a buffer, including the FCS.
+///
+/// The fields are decoded on demand, computing their offsets from the frame control
+/// field, so a received buffer can be inspected without copying it. The accessors panic
+/// if the buffer was not checked by `new_checked`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Frame<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+impl<T: AsRef<[u8]>> Frame<T> {
+    /// Wraps `buffer` without checking its contents.
+    pub fn new_unchecked(buffer: T) -> Self {
+        Frame { buffer }
+    }
+
+    /// Wraps `buffer`, checking that it holds the MHR and, if security is enabled, the
+    /// auxiliary security header. Fails if the buffer is truncated, a reserved address
+    /// mode or frame version is used or the auxiliary security header is malformed.
+    pub fn new_checked(buffer: T) -> Result<Self, ParseError> {
+        let frame = Self::new_unchecked(buffer);
+        frame.check().map(|_| frame)
+    }
+
+    fn check(&self) -> Result<(), ParseError> {
+        let buf = self.buffer.as_ref();
+        let (header, header_len) = Header::parse(buf)?;
+
+        let frame_control = header.frame_control;
+        if frame_control.security_enabled() {
+            AuxiliarySecurityHeader::parse(&buf[header_len..], frame_control.frame_version())
+                .map_err(|e| e.at(header_len))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Checks the FCS at the end of the buffer.
+    pub fn verify_fcs(&self, fcs: Fcs) -> bool {
+        fcs.verify(self.buffer.as_ref())
+    }
+
+    pub fn frame_control(&self) -> FrameControl {
+        let buf = self.buffer.as_ref();
+        FrameControl(u16::from_le_bytes([buf[0], buf[1]]))
+    }
+
+    fn offsets(&self) -> Offsets {
+        Offsets::new(&self.frame_control()).expect("the frame was checked")
+    }
+
+    fn u16_at(&self, offset: usize) -> u16 {
+        let buf = self.buffer.as_ref();
+        u16::from_le_bytes([buf[offset], buf[offset + 1]])
+    }
+
+    /// Absent if the sequence number is suppressed.
+    pub fn sequence_number(&self) -> Option<u8> {
+        self.offsets()
+            .sequence_number
+            .map(|offset| self.buffer.as_ref()[offset])
+    }
+
+    pub fn dst_pan_id(&self) -> Option<u16> {
+        self.offsets().dst_pan.map(|offset| self.u16_at(offset))
+    }
+
+    pub fn dst_addr(&self) -> Address {
+        let mode = self.frame_control().dst_addr_mode();
+        Address::parse(mode, &self.buffer.as_ref()[self.offsets().dst_addr..])
+            .expect("the frame was checked")
+    }
+
+    /// The source PAN identifier, absent if elided by PAN ID compression.
+    pub fn src_pan_id(&self) -> Option<u16> {
+        self.offsets().src_pan.map(|offset| self.u16_at(offset))
+    }
+
+    pub fn src_addr(&self) -> Address {
+        let mode = self.frame_control().src_addr_mode();
+        Address::parse(mode, &self.buffer.as_ref()[self.offsets().src_addr..])
+            .expect("the frame was checked")
+    }
+
+    /// The length of the MHR, excluding the auxiliary security header.
+    pub fn header_len(&self) -> usize {
+        self.offsets().end
+    }
+
+    /// The auxiliary security header and its length, if security is enabled.
+    pub fn auxiliary_security_header(&self) -> Option<(AuxiliarySecurityHeader, usize)> {
+        let frame_control = self.frame_control();
+        if !frame_control.security_enabled() {
+            return None;
+        }
+
+        let buf = &self.buffer.as_ref()[self.header_len()..];
+        let aux = AuxiliarySecurityHeader::parse(buf, frame_control.frame_version())
+            .expect("the frame was checked");
+        Some(aux)
+    }
+
+    /// The offset of the IEs and MAC payload following the MHR and auxiliary security
+    /// header.
+    pub fn payload_offset(&self) -> usize {
+        self.header_len() + self.auxiliary_security_header().map_or(0, |(_, len)| len)
+    }
+
+    /// The IEs and MAC payload, excluding the FCS. Returns `None` if the buffer is too
+    /// short for the FCS.
+    pub fn payload(&self, fcs: Fcs) -> Option<&[u8]> {
+        let buf = self.buffer.as_ref();
+        buf.get(self.payload_offset()..buf.len().checked_sub(fcs.size())?)
+    }
+}
+
+impl<'a> Frame<&'a [u8]> {
+    /// The IEs and MAC payload, borrowed for the lifetime of the buffer rather than the
+    /// view.
+    pub fn into_payload(self, fcs: Fcs) -> Option<&'a [u8]> {
+        let range = self.payload_offset()..self.buffer.len().checked_sub(fcs.size())?;
+        self.buffer.get(range)
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Frame<T> {
+    /// Computes the FCS over the first `len` octets of the buffer and writes it after
+    /// them. Returns the length of the frame including the FCS, or `None` if the buffer
+    /// is too short.
+    pub fn append_fcs(&mut self, len: usize, fcs: Fcs) -> Option<usize> {
+        fcs.append(self.buffer.as_mut(), len)
+    }
+
+    /// Sets the sequence number. Returns `None` if it is suppressed.
+    pub fn set_sequence_number(&mut self, sequence_number: u8) -> Option<()> {
+        let offset = self.offsets().sequence_number?;
+        self.buffer.as_mut()[offset] = sequence_number;
+        Some(())
+    }
+
+    /// Sets the destination PAN identifier. Returns `None` if it is not present.
+    pub fn set_dst_pan_id(&mut self, pan_id: u16) -> Option<()> {
+        let offset = self.offsets().dst_pan?;
+        self.buffer.as_mut()[offset..offset + 2].copy_from_slice(&pan_id.to_le_bytes());
+        Some(())
+    }
+
+    /// Sets the destination address. Returns `None` if `address` does not match the
+    /// destination addressing mode.
+    pub fn set_dst_addr(&mut self, address: Address) -> Option<()> {
+        if address.mode() != self.frame_control().dst_addr_mode() {
+            return None;
+        }
+
+        let offset = self.offsets().dst_addr;
+        address.emit(&mut self.buffer.as_mut()[offset..]);
+        Some(())
+    }
+
+    /// Sets the source PAN identifier. Returns `None` if it is not present.
+    pub fn set_src_pan_id(&mut self, pan_id: u16) -> Option<()> {
+        let offset = self.offsets().src_pan?;
+        self.buffer.as_mut()[offset..offset + 2].copy_from_slice(&pan_id.to_le_bytes());
+        Some(())
+    }
+
+    /// Sets the source address. Returns `None` if `address` does not match the source
+    /// addressing mode.
+    pub fn set_src_addr(&mut self, address: Address) -> Option<()> {
+        if address.mode() != self.frame_control().src_addr_mode() {
+            return None;
+        }
+
+        let offset = self.offsets().src_addr;
+        address.emit(&mut self.buffer.as_mut()[offset..]);
+        Some(())
+    }
+
+    /// The IEs and MAC payload, excluding the FCS, for modification in place.
+    pub fn payload_mut(&mut self, fcs: Fcs) -> Option<&mut [u8]> {
+        let start = self.payload_offset();
+        let buf = self.buffer.as_mut();
+        let end = buf.len().checked_sub(fcs.size())?;
+        buf.get_mut(start..end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secured_data_frame() {
+        // the secured data frame of Annex C of IEEE 802.15.4-2006
+        let mut buf = [
+            0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x04, 0x05, 0x00, 0x00, 0x00, 0xd4, 0x3e,
+            0x02, 0x2b, 0x00, 0x00,
+        ];
+        Frame::new_unchecked(&mut buf[..]).append_fcs(30, Fcs::Crc16);
+
+        let frame = Frame::new_checked(&buf[..]).unwrap();
+        assert!(frame.verify_fcs(Fcs::Crc16));
+        assert_eq!(frame.sequence_number(), Some(0x84));
+        assert_eq!(frame.dst_pan_id(), Some(0x4321));
+        assert_eq!(frame.dst_addr(), Address::Extended(0xacde_4800_0000_0002));
+        assert_eq!(frame.src_pan_id(), None);
+        assert_eq!(frame.src_addr(), Address::Extended(0xacde_4800_0000_0001));
+        assert_eq!(frame.header_len(), 21);
+        assert_eq!(
+            frame.auxiliary_security_header().unwrap().0.frame_counter,
+            Some(5)
+        );
+        assert_eq!(frame.into_payload(Fcs::Crc16), Some(&buf[26..30]));
+
+        assert_eq!(
+            Frame::new_checked(&buf[..24]),
+            Err(ParseError::Truncated { offset: 22 })
+        );
+    }
+
+    #[test]
+    fn modify_in_place() {
+        // a data frame of frame version 2006 between short addresses of the same PAN
+        let mut buf = [
+            0x41, 0x98, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut frame = Frame::new_checked(&mut buf[..]).unwrap();
+
+        assert_eq!(frame.set_sequence_number(0x84), Some(()));
+        assert_eq!(frame.set_dst_pan_id(0x4321), Some(()));
+        assert_eq!(frame.set_dst_addr(Address::Short(0x0002)), Some(()));
+        assert_eq!(frame.set_src_addr(Address::Short(0x0001)), Some(()));
+        frame
+            .payload_mut(Fcs::Crc16)
+            .unwrap()
+            .copy_from_slice(&[0x61, 0x62]);
+        assert_eq!(frame.append_fcs(11, Fcs::Crc16), Some(13));
+
+        assert_eq!(frame.sequence_number(), Some(0x84));
+        assert_eq!(frame.dst_pan_id(), Some(0x4321));
+        assert_eq!(frame.dst_addr(), Address::Short(0x0002));
+        assert_eq!(frame.src_pan_id(), None);
+        assert_eq!(frame.src_addr(), Address::Short(0x0001));
+        assert_eq!(frame.payload(Fcs::Crc16), Some(&[0x61, 0x62][..]));
+        assert!(frame.verify_fcs(Fcs::Crc16));
+        assert_eq!(
+            &buf[..11],
+            &[0x41, 0x98, 0x84, 0x21, 0x43, 0x02, 0x00, 0x01, 0x00, 0x61, 0x62][..]
+        );
+    }
+
+    #[test]
+    fn absent_fields() {
+        // a data frame of the current frame version suppressing the sequence number and
+        // eliding the source PAN identifier
+        let mut buf = [0x41, 0xa9, 0x21, 0x43, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00];
+        let mut frame = Frame::new_checked(&mut buf[..]).unwrap();
+
+        assert_eq!(frame.set_sequence_number(0x84), None);
+        assert_eq!(frame.set_src_pan_id(0x4321), None);
+        assert_eq!(
+            frame.set_dst_addr(Address::Extended(0xacde_4800_0000_0002)),
+            None
+        );
+        assert_eq!(frame.set_src_addr(Address::None), None);
+        assert_eq!(frame.append_fcs(9, Fcs::Crc16), None);
+
+        assert_eq!(frame.sequence_number(), None);
+        assert_eq!(frame.src_pan_id(), None);
+        assert_eq!(frame.dst_addr(), Address::Short(0x0002));
+        assert_eq!(frame.src_addr(), Address::Short(0x0001));
+        assert_eq!(frame.payload_mut(Fcs::Crc16), Some(&mut [][..]));
+        assert_eq!(
+            &buf[..8],
+            &[0x41, 0xa9, 0x21, 0x43, 0x02, 0x00, 0x01, 0x00][..]
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let mut buf = [0x41, 0x98, 0x84, 0x21, 0x43, 0x02, 0x00, 0x01];
+
+        assert_eq!(
+            Frame::new_checked(&mut buf[..]),
+            Err(ParseError::Truncated { offset: 7 })
+        );
+        assert_eq!(
+            Frame::new_checked(&mut buf[..1]),
+            Err(ParseError::Truncated { offset: 0 })
+        );
+    }
+}
@@ -0,0 +1,365 @@
+//! Decoding and encoding of the MAC header (MHR).
+
+use core::convert::TryInto;
+
+use crate::error::{get, ParseError};
+
+pub use crate::layout::FrameControl;
+
+/// The length of the frame control field.
+pub const FRAME_CONTROL_LEN: usize = 2;
+
+/// The frame types of the frame control field.
+pub mod frame_type {
+    pub const BEACON: u8 = 0b000;
+    pub const DATA: u8 = 0b001;
+    pub const ACKNOWLEDGEMENT: u8 = 0b010;
+    pub const MAC_COMMAND: u8 = 0b011;
+    pub const MULTIPURPOSE: u8 = 0b101;
+    pub const FRAGMENT: u8 = 0b110;
+    pub const EXTENDED: u8 = 0b111;
+}
+
+/// The frame versions of the frame control field.
+pub mod frame_version {
+    pub const VERSION_2003: u8 = 0b00;
+    pub const VERSION_2006: u8 = 0b01;
+    pub const CURRENT: u8 = 0b10;
+}
+
+/// An address of the MHR, corresponding to the `address` alternatives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Address {
+    None,
+    Short(u16),
+    Extended(u64),
+}
+
+impl Address {
+    const MODE_NONE: u8 = 0b00;
+    const MODE_SHORT: u8 = 0b10;
+    const MODE_EXTENDED: u8 = 0b11;
+
+    /// The address mode of the frame control field which selects this address.
+    pub fn mode(&self) -> u8 {
+        match self {
+            Address::None => Self::MODE_NONE,
+            Address::Short(_) => Self::MODE_SHORT,
+            Address::Extended(_) => Self::MODE_EXTENDED,
+        }
+    }
+
+    /// The encoded length of an address using `mode`, or `None` for the reserved mode.
+    pub fn len_of_mode(mode: u8) -> Option<usize> {
+        match mode {
+            Self::MODE_NONE => Some(0),
+            Self::MODE_SHORT => Some(2),
+            Self::MODE_EXTENDED => Some(8),
+            _ => None,
+        }
+    }
+
+    pub fn buffer_len(&self) -> usize {
+        match self {
+            Address::None => 0,
+            Address::Short(_) => 2,
+            Address::Extended(_) => 8,
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Address::None
+    }
+
+    /// Decodes an address using `mode` at the start of `buf`. Fails if `buf` is too short
+    /// or `mode` is reserved.
+    pub(crate) fn parse(mode: u8, buf: &[u8]) -> Result<Self, ParseError> {
+        Ok(match mode {
+            Self::MODE_NONE => Address::None,
+            Self::MODE_SHORT => {
+                Address::Short(u16::from_le_bytes(get(buf, 0, 2)?.try_into().unwrap()))
+            }
+            Self::MODE_EXTENDED => {
+                Address::Extended(u64::from_le_bytes(get(buf, 0, 8)?.try_into().unwrap()))
+            }
+            _ => return Err(ParseError::ReservedAddressMode { offset: 0 }),
+        })
+    }
+
+    pub(crate) fn emit(&self, buf: &mut [u8]) {
+        match self {
+            Address::None => (),
+            Address::Short(address) => buf[..2].copy_from_slice(&address.to_le_bytes()),
+            Address::Extended(address) => buf[..8].copy_from_slice(&address.to_le_bytes()),
+        }
+    }
+}
+
+/// A decoded MAC header, excluding the auxiliary security header and header IEs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub frame_control: FrameControl,
+    /// Absent if the sequence number is suppressed.
+    pub sequence_number: Option<u8>,
+    pub dst_pan: Option<u16>,
+    pub dst_address: Address,
+    pub src_pan: Option<u16>,
+    pub src_address: Address,
+}
+
+impl Header {
+    /// Decodes the header at the start of `buf`, returning the header and its length.
+    /// Fails if `buf` is truncated or a reserved address mode or frame version is used.
+    pub fn parse(buf: &[u8]) -> Result<(Self, usize), ParseError> {
+        let frame_control = FrameControl(u16::from_le_bytes(
+            get(buf, 0, FRAME_CONTROL_LEN)?.try_into().unwrap(),
+        ));
+        let (dst_pan_present, src_pan_present) = frame_control
+            .pan_ids_present()
+            .ok_or(ParseError::ReservedFrameVersion { offset: 0 })?;
+        for &mode in &[frame_control.dst_addr_mode(), frame_control.src_addr_mode()] {
+            Address::len_of_mode(mode).ok_or(ParseError::ReservedAddressMode { offset: 0 })?;
+        }
+
+        let mut offset = FRAME_CONTROL_LEN;
+        let sequence_number = if frame_control.sequence_number_present() {
+            offset += 1;
+            Some(get(buf, offset - 1, offset)?[0])
+        } else {
+            None
+        };
+
+        let pan = |present: bool, offset: &mut usize| -> Result<Option<u16>, ParseError> {
+            if !present {
+                return Ok(None);
+            }
+            let pan = u16::from_le_bytes(get(buf, *offset, *offset + 2)?.try_into().unwrap());
+            *offset += 2;
+            Ok(Some(pan))
+        };
+
+        let dst_pan = pan(dst_pan_present, &mut offset)?;
+        let dst_address = Address::parse(frame_control.dst_addr_mode(), &buf[offset..])
+            .map_err(|e| e.at(offset))?;
+        offset += dst_address.buffer_len();
+
+        let src_pan = pan(src_pan_present, &mut offset)?;
+        let src_address = Address::parse(frame_control.src_addr_mode(), &buf[offset..])
+            .map_err(|e| e.at(offset))?;
+        offset += src_address.buffer_len();
+
+        let header = Header {
+            frame_control,
+            sequence_number,
+            dst_pan,
+            dst_address,
+            src_pan,
+            src_address,
+        };
+
+        Ok((header, offset))
+    }
+
+    /// The encoded length of the header.
+    pub fn buffer_len(&self) -> usize {
+        let pan_len = |pan: Option<u16>| if pan.is_some() { 2 } else { 0 };
+
+        FRAME_CONTROL_LEN
+            + if self.sequence_number.is_some() { 1 } else { 0 }
+            + pan_len(self.dst_pan)
+            + self.dst_address.buffer_len()
+            + pan_len(self.src_pan)
+            + self.src_address.buffer_len()
+    }
+
+    /// Encodes the header into the start of `buf`. The sequence number suppression,
+    /// address modes and PAN ID compression of the frame control field are derived from
+    /// the fields. Returns the length of the header, or `None` if `buf` is too short or
+    /// the frame version cannot express the combination of fields.
+    pub fn emit(&self, buf: &mut [u8]) -> Option<usize> {
+        let len = self.buffer_len();
+        let buf = buf.get_mut(..len)?;
+
+        let mut frame_control = self.frame_control;
+        frame_control.set_dst_addr_mode(self.dst_address.mode());
+        frame_control.set_src_addr_mode(self.src_address.mode());
+
+        let pans = (self.dst_pan.is_some(), self.src_pan.is_some());
+        let compression = [false, true].iter().copied().find(|&compression| {
+            frame_control.set_pan_id_compression(compression);
+            frame_control.pan_ids_present() == Some(pans)
+        })?;
+        frame_control.set_pan_id_compression(compression);
+
+        frame_control.set_seq_nr_suppressed(self.sequence_number.is_none());
+        if frame_control.sequence_number_present() != self.sequence_number.is_some() {
+            return None;
+        }
+        buf[..FRAME_CONTROL_LEN].copy_from_slice(&frame_control.0.to_le_bytes());
+
+        let mut offset = FRAME_CONTROL_LEN;
+        if let Some(sequence_number) = self.sequence_number {
+            buf[offset] = sequence_number;
+            offset += 1;
+        }
+
+        for &(pan, address) in &[
+            (self.dst_pan, &self.dst_address),
+            (self.src_pan, &self.src_address),
+        ] {
+            if let Some(pan) = pan {
+                buf[offset..offset + 2].copy_from_slice(&pan.to_le_bytes());
+                offset += 2;
+            }
+            address.emit(&mut buf[offset..]);
+            offset += address.buffer_len();
+        }
+
+        Some(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONE: u8 = Address::MODE_NONE;
+    const SHORT: u8 = Address::MODE_SHORT;
+    const EXTENDED: u8 = Address::MODE_EXTENDED;
+
+    fn frame_control(version: u8, dst_mode: u8, src_mode: u8, compression: bool) -> FrameControl {
+        let mut frame_control = FrameControl::default();
+        frame_control.set_frame_version(version);
+        frame_control.set_dst_addr_mode(dst_mode);
+        frame_control.set_src_addr_mode(src_mode);
+        frame_control.set_pan_id_compression(compression);
+        frame_control
+    }
+
+    #[test]
+    fn pan_id_compression_table() {
+        // the rows of Table 7-2 of IEEE 802.15.4-2015
+        let rows = [
+            (NONE, NONE, false, (false, false)),
+            (NONE, NONE, true, (true, false)),
+            (SHORT, NONE, false, (true, false)),
+            (EXTENDED, NONE, false, (true, false)),
+            (SHORT, NONE, true, (false, false)),
+            (EXTENDED, NONE, true, (false, false)),
+            (NONE, SHORT, false, (false, true)),
+            (NONE, EXTENDED, false, (false, true)),
+            (NONE, SHORT, true, (false, false)),
+            (NONE, EXTENDED, true, (false, false)),
+            (EXTENDED, EXTENDED, false, (true, false)),
+            (EXTENDED, EXTENDED, true, (false, false)),
+            (SHORT, SHORT, false, (true, true)),
+            (SHORT, EXTENDED, false, (true, true)),
+            (EXTENDED, SHORT, false, (true, true)),
+            (SHORT, EXTENDED, true, (true, false)),
+            (EXTENDED, SHORT, true, (true, false)),
+            (SHORT, SHORT, true, (true, false)),
+        ];
+
+        for &(dst_mode, src_mode, compression, present) in &rows {
+            let frame_control =
+                frame_control(frame_version::CURRENT, dst_mode, src_mode, compression);
+            assert_eq!(
+                frame_control.pan_ids_present(),
+                Some(present),
+                "{:?}",
+                (dst_mode, src_mode, compression)
+            );
+        }
+    }
+
+    #[test]
+    fn pan_id_compression_legacy() {
+        for &version in &[frame_version::VERSION_2003, frame_version::VERSION_2006] {
+            let rows = [
+                (NONE, NONE, false, (false, false)),
+                (SHORT, NONE, false, (true, false)),
+                (NONE, EXTENDED, false, (false, true)),
+                (EXTENDED, EXTENDED, false, (true, true)),
+                (SHORT, EXTENDED, true, (true, false)),
+                (EXTENDED, EXTENDED, true, (true, false)),
+            ];
+
+            for &(dst_mode, src_mode, compression, present) in &rows {
+                let frame_control = frame_control(version, dst_mode, src_mode, compression);
+                assert_eq!(frame_control.pan_ids_present(), Some(present));
+            }
+        }
+
+        let frame_control = frame_control(0b11, SHORT, SHORT, false);
+        assert_eq!(frame_control.pan_ids_present(), None);
+    }
+
+    #[test]
+    fn sequence_number_suppression() {
+        let mut frame_control = frame_control(frame_version::CURRENT, SHORT, NONE, false);
+        frame_control.set_seq_nr_suppressed(true);
+        let header = Header {
+            frame_control,
+            sequence_number: None,
+            dst_pan: Some(0xabcd),
+            dst_address: Address::Short(0xffff),
+            src_pan: None,
+            src_address: Address::None,
+        };
+
+        let mut buf = [0; 6];
+        assert_eq!(header.emit(&mut buf), Some(6));
+        assert_eq!(buf, [0x00, 0x29, 0xcd, 0xab, 0xff, 0xff]);
+        assert_eq!(Header::parse(&buf), Ok((header, 6)));
+
+        // the bit is reserved in earlier versions, which always carry a sequence number
+        frame_control.set_frame_version(frame_version::VERSION_2006);
+        let header = Header {
+            frame_control,
+            ..header
+        };
+        assert_eq!(header.emit(&mut buf), None);
+
+        let buf = [0x00, 0x19, 0x42, 0xcd, 0xab, 0xff, 0xff];
+        let (header, len) = Header::parse(&buf).unwrap();
+        assert_eq!(header.sequence_number, Some(0x42));
+        assert_eq!(header.dst_pan, Some(0xabcd));
+        assert_eq!(len, 7);
+    }
+
+    #[test]
+    fn reserved_address_mode() {
+        for &(dst_mode, src_mode) in &[(0b01, SHORT), (SHORT, 0b01)] {
+            let frame_control = frame_control(frame_version::CURRENT, dst_mode, src_mode, false);
+            let mut buf = [0; 16];
+            buf[..2].copy_from_slice(&frame_control.0.to_le_bytes());
+
+            assert_eq!(
+                Header::parse(&buf),
+                Err(ParseError::ReservedAddressMode { offset: 0 })
+            );
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        // the MHR of the secured data frame of Annex C of IEEE 802.15.4-2006, missing its
+        // source address
+        let buf = [
+            0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde,
+        ];
+
+        assert_eq!(
+            Header::parse(&buf),
+            Err(ParseError::Truncated { offset: 5 })
+        );
+        assert_eq!(
+            Header::parse(&[0x41, 0x94]),
+            Err(ParseError::ReservedAddressMode { offset: 0 })
+        );
+        assert_eq!(
+            Header::parse(&[0x41, 0x3c]),
+            Err(ParseError::ReservedFrameVersion { offset: 0 })
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! The contents of the header IEs listed in `Element_id`.
+
+use core::convert::TryInto;
+
+use crate::error::{get, ParseError};
+
+use super::header::HeaderIe;
+
+/// The element IDs of header IEs, see `Element_id`.
+pub mod element_ids {
+    pub const VENDOR_SPECIFIC: u8 = 0x00;
+    pub const CSL: u8 = 0x1a;
+    pub const RIT: u8 = 0x1b;
+    pub const DSME_PAN_DESCRIPTOR: u8 = 0x1c;
+    pub const RENDEZVOUS_TIME: u8 = 0x1d;
+    pub const TIME_CORRECTION: u8 = 0x1e;
+    pub const EXTENDED_DSME_PAN_DESCRIPTOR: u8 = 0x21;
+    pub const FRAGMENT_SEQUENCE_CONTEXT: u8 = 0x22;
+    pub const SIMPLIFIED_SUPERFRAME: u8 = 0x23;
+    pub const SIMPLIFIED_GTS: u8 = 0x24;
+    pub const LECIM_CAPABILITIES: u8 = 0x25;
+    pub const TRLE_DESCRIPTOR: u8 = 0x26;
+    pub const RCC_CAPABILITIES: u8 = 0x27;
+    pub const RCCN_DESCRIPTOR: u8 = 0x28;
+    pub const GLOBAL_TIME: u8 = 0x29;
+    pub const DA: u8 = 0x2b;
+}
+
+/// The content of the CSL IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CslIe {
+    /// The time until the next CSL sample, in units of 10 symbols.
+    pub phase: u16,
+    /// The CSL sampling period, in units of 10 symbols.
+    pub period: u16,
+    /// Only included in CSL IEs of multipurpose wake-up frames.
+    pub rendezvous_time: Option<u16>,
+}
+
+/// The content of the RIT IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RitIe {
+    pub time_to_first_listen: u8,
+    pub number_of_repeat_listen: u8,
+    pub repeat_listen_interval: u16,
+}
+
+/// The content of the Rendezvous Time IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RendezvousTimeIe {
+    /// The time until the payload frame follows the wake-up frame, in units of 10
+    /// symbols.
+    pub rendezvous_time: u16,
+    pub wake_up_interval: Option<u16>,
+}
+
+/// The content of the Time Correction IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeCorrectionIe {
+    /// The time correction in microseconds, a signed 12-bit value.
+    pub time_sync: i16,
+    /// Set if the acknowledged frame was not accepted.
+    pub nack: bool,
+}
+
+impl TimeCorrectionIe {
+    pub const CONTENT_LEN: usize = 2;
+
+    /// Encodes the content of the IE, clamping the time correction to 12 bits.
+    pub fn to_bytes(&self) -> [u8; Self::CONTENT_LEN] {
+        let time_sync = self.time_sync.max(-2048).min(2047) as u16 & 0x0fff;
+        let nack = if self.nack { 0x8000 } else { 0 };
+
+        (time_sync | nack).to_le_bytes()
+    }
+}
+
+/// The content of the Simplified Superframe Specification IE. The superframe and CFP
+/// specifications are kept as their raw encoding, see `Superframe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimplifiedSuperframeIe {
+    pub timestamp: u16,
+    pub superframe: u16,
+    pub cfp_specification: u16,
+}
+
+/// The typed content of a header IE.
+///
+/// The IEs of the DSME, LECIM, TRLE and RCC extensions, the Fragment Sequence Context
+/// Description IE and the DA IE carry their raw content. They are out of scope: the
+/// crate implements none of the procedures using them, and their layouts depend on
+/// PIB attributes or capability fields of those extensions, such as the length of the
+/// DSME beacon bitmap or the fields announced by the LECIM capabilities, so the
+/// content cannot be decoded from the IE alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaderIeContent<'a> {
+    VendorSpecific {
+        oui: [u8; 3],
+        content: &'a [u8],
+    },
+    Csl(CslIe),
+    Rit(RitIe),
+    DsmePanDescriptor(&'a [u8]),
+    RendezvousTime(RendezvousTimeIe),
+    TimeCorrection(TimeCorrectionIe),
+    ExtendedDsmePanDescriptor(&'a [u8]),
+    FragmentSequenceContext(&'a [u8]),
+    SimplifiedSuperframe(SimplifiedSuperframeIe),
+    SimplifiedGts(&'a [u8]),
+    LecimCapabilities(&'a [u8]),
+    TrleDescriptor(&'a [u8]),
+    RccCapabilities(&'a [u8]),
+    RccnDescriptor(&'a [u8]),
+    /// The global time in seconds since 1970-01-01T00:00:00Z.
+    GlobalTime(u32),
+    Da(&'a [u8]),
+    /// An IE with an element ID that is not listed in `Element_id`.
+    Unknown(u8, &'a [u8]),
+}
+
+impl<'a> HeaderIeContent<'a> {
+    /// Decodes the content of `ie` according to its element ID. Fails if the length of
+    /// the content does not match the layout of the IE, with the offset relative to the
+    /// start of the content.
+    pub fn parse(ie: &HeaderIe<'a>) -> Result<Self, ParseError> {
+        // the lengths are checked before the fields are decoded
+        let u16_at =
+            |offset: usize| u16::from_le_bytes([ie.content[offset], ie.content[offset + 1]]);
+        let optional_u16_at =
+            |offset: usize| ie.content.get(offset..offset + 2).map(|_| u16_at(offset));
+        let len = ie.content.len();
+
+        let content = match ie.element_id {
+            element_ids::VENDOR_SPECIFIC => HeaderIeContent::VendorSpecific {
+                oui: get(ie.content, 0, 3)?.try_into().unwrap(),
+                content: &ie.content[3..],
+            },
+            element_ids::CSL if len == 4 || len == 6 => HeaderIeContent::Csl(CslIe {
+                phase: u16_at(0),
+                period: u16_at(2),
+                rendezvous_time: optional_u16_at(4),
+            }),
+            element_ids::RIT if len == 4 => HeaderIeContent::Rit(RitIe {
+                time_to_first_listen: ie.content[0],
+                number_of_repeat_listen: ie.content[1],
+                repeat_listen_interval: u16_at(2),
+            }),
+            element_ids::DSME_PAN_DESCRIPTOR => HeaderIeContent::DsmePanDescriptor(ie.content),
+            element_ids::RENDEZVOUS_TIME if len == 2 || len == 4 => {
+                HeaderIeContent::RendezvousTime(RendezvousTimeIe {
+                    rendezvous_time: u16_at(0),
+                    wake_up_interval: optional_u16_at(2),
+                })
+            }
+            element_ids::TIME_CORRECTION if len == TimeCorrectionIe::CONTENT_LEN => {
+                let time_sync_info = u16_at(0);
+                HeaderIeContent::TimeCorrection(TimeCorrectionIe {
+                    // sign extend the 12-bit value
+                    time_sync: ((time_sync_info << 4) as i16) >> 4,
+                    nack: time_sync_info & 0x8000 != 0,
+                })
+            }
+            element_ids::EXTENDED_DSME_PAN_DESCRIPTOR => {
+                HeaderIeContent::ExtendedDsmePanDescriptor(ie.content)
+            }
+            element_ids::FRAGMENT_SEQUENCE_CONTEXT => {
+                HeaderIeContent::FragmentSequenceContext(ie.content)
+            }
+            element_ids::SIMPLIFIED_SUPERFRAME if len == 6 => {
+                HeaderIeContent::SimplifiedSuperframe(SimplifiedSuperframeIe {
+                    timestamp: u16_at(0),
+                    superframe: u16_at(2),
+                    cfp_specification: u16_at(4),
+                })
+            }
+            element_ids::SIMPLIFIED_GTS => HeaderIeContent::SimplifiedGts(ie.content),
+            element_ids::LECIM_CAPABILITIES => HeaderIeContent::LecimCapabilities(ie.content),
+            element_ids::TRLE_DESCRIPTOR => HeaderIeContent::TrleDescriptor(ie.content),
+            element_ids::RCC_CAPABILITIES => HeaderIeContent::RccCapabilities(ie.content),
+            element_ids::RCCN_DESCRIPTOR => HeaderIeContent::RccnDescriptor(ie.content),
+            element_ids::GLOBAL_TIME if len == 4 => {
+                HeaderIeContent::GlobalTime(u32::from_le_bytes(ie.content.try_into().unwrap()))
+            }
+            element_ids::DA => HeaderIeContent::Da(ie.content),
+            element_ids::CSL
+            | element_ids::RIT
+            | element_ids::RENDEZVOUS_TIME
+            | element_ids::TIME_CORRECTION
+            | element_ids::SIMPLIFIED_SUPERFRAME
+            | element_ids::GLOBAL_TIME => return Err(ParseError::InvalidField { offset: 0 }),
+            id => HeaderIeContent::Unknown(id, ie.content),
+        };
+
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time_correction(content: &[u8]) -> Result<HeaderIeContent<'_>, ParseError> {
+        HeaderIeContent::parse(&HeaderIe {
+            element_id: element_ids::TIME_CORRECTION,
+            content,
+        })
+    }
+
+    #[test]
+    fn time_correction_ie() {
+        let ie = |time_sync, nack| {
+            Ok(HeaderIeContent::TimeCorrection(TimeCorrectionIe {
+                time_sync,
+                nack,
+            }))
+        };
+
+        assert_eq!(time_correction(&[0xff, 0x8f]), ie(-1, true));
+        assert_eq!(time_correction(&[0x00, 0x08]), ie(-2048, false));
+        assert_eq!(time_correction(&[0xff, 0x07]), ie(2047, false));
+        assert_eq!(time_correction(&[0x00, 0x80]), ie(0, true));
+        assert_eq!(
+            time_correction(&[0x00]),
+            Err(ParseError::InvalidField { offset: 0 })
+        );
+
+        let encoded = TimeCorrectionIe {
+            time_sync: -1,
+            nack: true,
+        }
+        .to_bytes();
+        assert_eq!(encoded, [0xff, 0x8f]);
+
+        // out of range corrections are clamped to 12 bits
+        let encoded = TimeCorrectionIe {
+            time_sync: 3000,
+            nack: false,
+        }
+        .to_bytes();
+        assert_eq!(encoded, [0xff, 0x07]);
+        let encoded = TimeCorrectionIe {
+            time_sync: -3000,
+            nack: false,
+        }
+        .to_bytes();
+        assert_eq!(encoded, [0x00, 0x08]);
+    }
+
+    fn parse(element_id: u8, content: &[u8]) -> Result<HeaderIeContent<'_>, ParseError> {
+        HeaderIeContent::parse(&HeaderIe {
+            element_id,
+            content,
+        })
+    }
+
+    #[test]
+    fn csl_ie() {
+        assert_eq!(
+            parse(element_ids::CSL, &[0x34, 0x12, 0x78, 0x56]),
+            Ok(HeaderIeContent::Csl(CslIe {
+                phase: 0x1234,
+                period: 0x5678,
+                rendezvous_time: None,
+            }))
+        );
+        assert_eq!(
+            parse(element_ids::CSL, &[0x34, 0x12, 0x78, 0x56, 0xbc, 0x9a]),
+            Ok(HeaderIeContent::Csl(CslIe {
+                phase: 0x1234,
+                period: 0x5678,
+                rendezvous_time: Some(0x9abc),
+            }))
+        );
+        assert_eq!(
+            parse(element_ids::CSL, &[0x34, 0x12, 0x78, 0x56, 0xbc]),
+            Err(ParseError::InvalidField { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn rit_ie() {
+        assert_eq!(
+            parse(element_ids::RIT, &[0x01, 0x02, 0x04, 0x03]),
+            Ok(HeaderIeContent::Rit(RitIe {
+                time_to_first_listen: 1,
+                number_of_repeat_listen: 2,
+                repeat_listen_interval: 0x0304,
+            }))
+        );
+        assert_eq!(
+            parse(element_ids::RIT, &[0x01, 0x02, 0x04]),
+            Err(ParseError::InvalidField { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn rendezvous_time_ie() {
+        assert_eq!(
+            parse(element_ids::RENDEZVOUS_TIME, &[0x34, 0x12]),
+            Ok(HeaderIeContent::RendezvousTime(RendezvousTimeIe {
+                rendezvous_time: 0x1234,
+                wake_up_interval: None,
+            }))
+        );
+        assert_eq!(
+            parse(element_ids::RENDEZVOUS_TIME, &[0x34, 0x12, 0x78, 0x56]),
+            Ok(HeaderIeContent::RendezvousTime(RendezvousTimeIe {
+                rendezvous_time: 0x1234,
+                wake_up_interval: Some(0x5678),
+            }))
+        );
+        assert_eq!(
+            parse(element_ids::RENDEZVOUS_TIME, &[0x34, 0x12, 0x78]),
+            Err(ParseError::InvalidField { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn simplified_superframe_ie() {
+        assert_eq!(
+            parse(
+                element_ids::SIMPLIFIED_SUPERFRAME,
+                &[0x34, 0x12, 0xff, 0xcf, 0x00, 0x00]
+            ),
+            Ok(HeaderIeContent::SimplifiedSuperframe(
+                SimplifiedSuperframeIe {
+                    timestamp: 0x1234,
+                    superframe: 0xcfff,
+                    cfp_specification: 0,
+                }
+            ))
+        );
+        assert_eq!(
+            parse(
+                element_ids::SIMPLIFIED_SUPERFRAME,
+                &[0x34, 0x12, 0xff, 0xcf]
+            ),
+            Err(ParseError::InvalidField { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn global_time_ie() {
+        assert_eq!(
+            parse(element_ids::GLOBAL_TIME, &[0x00, 0x5e, 0xd0, 0xb2]),
+            Ok(HeaderIeContent::GlobalTime(3_000_000_000))
+        );
+        assert_eq!(
+            parse(element_ids::GLOBAL_TIME, &[0x00, 0x5e, 0xd0, 0xb2, 0x00]),
+            Err(ParseError::InvalidField { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn vendor_specific_ie() {
+        assert_eq!(
+            parse(
+                element_ids::VENDOR_SPECIFIC,
+                &[0x00, 0x12, 0x4b, 0x01, 0x02]
+            ),
+            Ok(HeaderIeContent::VendorSpecific {
+                oui: [0x00, 0x12, 0x4b],
+                content: &[0x01, 0x02],
+            })
+        );
+        assert_eq!(
+            parse(element_ids::VENDOR_SPECIFIC, &[0x00, 0x12, 0x4b]),
+            Ok(HeaderIeContent::VendorSpecific {
+                oui: [0x00, 0x12, 0x4b],
+                content: &[],
+            })
+        );
+        assert_eq!(
+            parse(element_ids::VENDOR_SPECIFIC, &[0x00, 0x12]),
+            Err(ParseError::Truncated { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn raw_content() {
+        assert_eq!(
+            parse(element_ids::DSME_PAN_DESCRIPTOR, &[1, 2, 3]),
+            Ok(HeaderIeContent::DsmePanDescriptor(&[1, 2, 3]))
+        );
+        assert_eq!(parse(element_ids::DA, &[]), Ok(HeaderIeContent::Da(&[])));
+    }
+
+    #[test]
+    fn unknown_ie() {
+        let ie = HeaderIe {
+            element_id: 0x50,
+            content: &[1, 2],
+        };
+        assert_eq!(
+            HeaderIeContent::parse(&ie),
+            Ok(HeaderIeContent::Unknown(0x50, &[1, 2]))
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! The list of header IEs following the MHR of frames with `IE_Present` set.
+
+use core::convert::TryInto;
+
+use crate::error::{get, ParseError};
+
+/// The length of the descriptor of an IE.
+pub const IE_DESCRIPTOR_LEN: usize = 2;
+
+/// The element ID of the Header Termination 1 IE, which is followed by payload IEs.
+pub const HEADER_TERMINATION_1: u8 = 0x7e;
+/// The element ID of the Header Termination 2 IE, which is followed by the MAC payload.
+pub const HEADER_TERMINATION_2: u8 = 0x7f;
+
+pub use crate::layout::HeaderIeDescriptor;
+
+/// A header IE, see `HeaderIeDescriptor` for the layout of its descriptor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeaderIe<'a> {
+    pub element_id: u8,
+    pub content: &'a [u8],
+}
+
+/// What follows the header IE list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaderTermination {
+    /// The list is terminated by a Header Termination 1 IE, payload IEs follow.
+    PayloadIes,
+    /// The list is terminated by a Header Termination 2 IE, the MAC payload follows.
+    Payload,
+    /// The list extends to the end of the buffer without a termination IE.
+    None,
+}
+
+/// A validated list of header IEs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeaderIeList<'a> {
+    ies: &'a [u8],
+    termination: HeaderTermination,
+    rest: &'a [u8],
+}
+
+impl<'a> HeaderIeList<'a> {
+    /// Walks the header IEs at the start of `buf` up to and including the first header
+    /// termination IE. Fails if an IE is a payload IE or its content overruns `buf`.
+    pub fn parse(buf: &'a [u8]) -> Result<Self, ParseError> {
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let (element_id, len) = parse_descriptor(&buf[offset..]).map_err(|e| e.at(offset))?;
+            let end = offset + IE_DESCRIPTOR_LEN + len;
+            if end > buf.len() {
+                return Err(ParseError::IeOverrun { offset });
+            }
+
+            let termination = match element_id {
+                HEADER_TERMINATION_1 => HeaderTermination::PayloadIes,
+                HEADER_TERMINATION_2 => HeaderTermination::Payload,
+                _ => {
+                    offset = end;
+                    continue;
+                }
+            };
+
+            return Ok(HeaderIeList {
+                ies: &buf[..offset],
+                termination,
+                rest: &buf[end..],
+            });
+        }
+
+        Ok(HeaderIeList {
+            ies: buf,
+            termination: HeaderTermination::None,
+            rest: &buf[buf.len()..],
+        })
+    }
+
+    /// The number of octets occupied by the list, including the termination IE.
+    pub fn buffer_len(&self) -> usize {
+        let termination_len = match self.termination {
+            HeaderTermination::None => 0,
+            _ => IE_DESCRIPTOR_LEN,
+        };
+
+        self.ies.len() + termination_len
+    }
+
+    /// The header IEs of the list, excluding the termination IE.
+    pub fn iter(&self) -> HeaderIes<'a> {
+        HeaderIes { buf: self.ies }
+    }
+
+    pub fn termination(&self) -> HeaderTermination {
+        self.termination
+    }
+
+    /// The payload IEs and MAC payload following a Header Termination 1 IE.
+    pub fn payload_ies(&self) -> Option<&'a [u8]> {
+        match self.termination {
+            HeaderTermination::PayloadIes => Some(self.rest),
+            _ => None,
+        }
+    }
+
+    /// The MAC payload following the list, unless it is followed by payload IEs.
+    pub fn payload(&self) -> Option<&'a [u8]> {
+        match self.termination {
+            HeaderTermination::PayloadIes => None,
+            _ => Some(self.rest),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &HeaderIeList<'a> {
+    type Item = HeaderIe<'a>;
+    type IntoIter = HeaderIes<'a>;
+
+    fn into_iter(self) -> HeaderIes<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the IEs of a `HeaderIeList`.
+#[derive(Clone, Debug)]
+pub struct HeaderIes<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Iterator for HeaderIes<'a> {
+    type Item = HeaderIe<'a>;
+
+    fn next(&mut self) -> Option<HeaderIe<'a>> {
+        let (element_id, len) = parse_descriptor(self.buf).ok()?;
+        let end = IE_DESCRIPTOR_LEN + len;
+
+        let content = &self.buf[IE_DESCRIPTOR_LEN..end];
+        self.buf = &self.buf[end..];
+
+        Some(HeaderIe {
+            element_id,
+            content,
+        })
+    }
+}
+
+/// Encodes the descriptor of a header IE with `element_id` and `len` octets of content.
+/// Returns `None` if the content is too long.
+pub fn header_ie_descriptor(element_id: u8, len: usize) -> Option<[u8; IE_DESCRIPTOR_LEN]> {
+    if len > usize::from(HeaderIeDescriptor::LENGTH_MAX) {
+        return None;
+    }
+
+    let mut descriptor = HeaderIeDescriptor::default();
+    descriptor.set_length(len as u8);
+    descriptor.set_element_id(element_id);
+    Some(descriptor.0.to_le_bytes())
+}
+
+/// Decodes the element ID and content length of the header IE descriptor at the start
+/// of `buf`.
+fn parse_descriptor(buf: &[u8]) -> Result<(u8, usize), ParseError> {
+    let descriptor = HeaderIeDescriptor(u16::from_le_bytes(
+        get(buf, 0, IE_DESCRIPTOR_LEN)?.try_into().unwrap(),
+    ));
+    if descriptor.payload_ie() {
+        return Err(ParseError::UnexpectedIe { offset: 0 });
+    }
+
+    Ok((descriptor.element_id(), usize::from(descriptor.length())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminations() {
+        // a Time Correction IE and HT1, followed by payload IEs
+        let buf = [0x02, 0x0f, 0xff, 0x8f, 0x00, 0x3f, 0x01, 0x02];
+        let ies = HeaderIeList::parse(&buf).unwrap();
+
+        let mut iter = ies.iter();
+        assert_eq!(
+            iter.next(),
+            Some(HeaderIe {
+                element_id: 0x1e,
+                content: &[0xff, 0x8f],
+            })
+        );
+        assert_eq!(iter.next(), None);
+        assert_eq!(ies.termination(), HeaderTermination::PayloadIes);
+        assert_eq!(ies.buffer_len(), 6);
+        assert_eq!(ies.payload_ies(), Some(&buf[6..]));
+        assert_eq!(ies.payload(), None);
+
+        // HT2, followed by the MAC payload
+        let buf = [0x80, 0x3f, 0xaa];
+        let ies = HeaderIeList::parse(&buf).unwrap();
+        assert_eq!(ies.iter().count(), 0);
+        assert_eq!(ies.termination(), HeaderTermination::Payload);
+        assert_eq!(ies.buffer_len(), 2);
+        assert_eq!(ies.payload(), Some(&buf[2..]));
+
+        let ies = HeaderIeList::parse(&buf[..0]).unwrap();
+        assert_eq!(ies.termination(), HeaderTermination::None);
+        assert_eq!(ies.payload(), Some(&[][..]));
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            HeaderIeList::parse(&[0x02, 0x0f, 0xff, 0x8f, 0x04, 0x0f, 0x00, 0x00]),
+            Err(ParseError::IeOverrun { offset: 4 })
+        );
+        assert_eq!(
+            HeaderIeList::parse(&[0x02, 0x0f, 0xff, 0x8f, 0x00, 0x88]),
+            Err(ParseError::UnexpectedIe { offset: 4 })
+        );
+        assert_eq!(
+            HeaderIeList::parse(&[0x02, 0x0f, 0xff, 0x8f, 0x00]),
+            Err(ParseError::Truncated { offset: 4 })
+        );
+    }
+
+    #[test]
+    fn descriptor() {
+        assert_eq!(header_ie_descriptor(0x1e, 2), Some([0x02, 0x0f]));
+        assert_eq!(
+            header_ie_descriptor(HEADER_TERMINATION_1, 0),
+            Some([0x00, 0x3f])
+        );
+        assert_eq!(header_ie_descriptor(0x1e, 0x80), None);
+    }
+}
@@ -0,0 +1,181 @@
+//! The IE section of a frame, between the MHR and the MAC payload.
+
+use crate::error::ParseError;
+
+use super::header::{HeaderIeList, IE_DESCRIPTOR_LEN};
+use super::payload::{group_ids, NestedIe, NestedIes, PayloadIeList, PayloadIes};
+
+/// The header and payload IEs of a frame together with the MAC payload following them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameIes<'a> {
+    pub header_ies: Option<HeaderIeList<'a>>,
+    pub payload_ies: Option<PayloadIeList<'a>>,
+    pub payload: &'a [u8],
+}
+
+impl<'a> FrameIes<'a> {
+    /// Splits `buf`, the part of the frame following the MHR and auxiliary security
+    /// header and preceding the FCS, into its IEs and the MAC payload. `ie_present` is
+    /// the `IE_Present` bit of the frame control field. Fails if the IE lists are
+    /// malformed.
+    pub fn parse(buf: &'a [u8], ie_present: bool) -> Result<Self, ParseError> {
+        if !ie_present {
+            return Ok(FrameIes {
+                header_ies: None,
+                payload_ies: None,
+                payload: buf,
+            });
+        }
+
+        let header_ies = HeaderIeList::parse(buf)?;
+        let (payload_ies, payload) = match header_ies.payload_ies() {
+            Some(rest) => {
+                let payload_ies =
+                    PayloadIeList::parse(rest).map_err(|e| e.at(header_ies.buffer_len()))?;
+                (Some(payload_ies), payload_ies.payload())
+            }
+            None => (None, header_ies.payload().unwrap_or(&buf[buf.len()..])),
+        };
+
+        Ok(FrameIes {
+            header_ies: Some(header_ies),
+            payload_ies,
+            payload,
+        })
+    }
+
+    /// The offset of the MAC payload relative to the start of the IE section.
+    pub fn payload_offset(&self) -> usize {
+        self.header_ies.map_or(0, |ies| ies.buffer_len())
+            + self.payload_ies.map_or(0, |ies| ies.buffer_len())
+    }
+
+    /// The nested IEs of all MLME payload IEs, together with their offsets relative to
+    /// the start of the IE section. MLME IEs whose nested IEs overrun the content are
+    /// reported as errors.
+    pub fn mlme_ies(&self) -> MlmeIes<'a> {
+        MlmeIes {
+            payload_ies: self.payload_ies.map(|ies| ies.iter()),
+            start: self.header_ies.map_or(0, |ies| ies.buffer_len()),
+            nested: None,
+        }
+    }
+}
+
+/// An iterator over the nested IEs of the MLME IEs of a frame, see `FrameIes::mlme_ies`.
+#[derive(Clone, Debug)]
+pub struct MlmeIes<'a> {
+    payload_ies: Option<PayloadIes<'a>>,
+    /// The offset of the payload IEs in the IE section.
+    start: usize,
+    /// The nested IEs of the current MLME IE and the offset of its content.
+    nested: Option<(usize, NestedIes<'a>)>,
+}
+
+impl<'a> Iterator for MlmeIes<'a> {
+    type Item = Result<(usize, NestedIe<'a>), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((content_offset, nested)) = &mut self.nested {
+                let offset = *content_offset + nested.offset();
+                match nested.next() {
+                    Some(ie) => return Some(Ok((offset, ie))),
+                    None => self.nested = None,
+                }
+            }
+
+            let payload_ies = self.payload_ies.as_mut()?;
+            let content_offset = self.start + payload_ies.offset() + IE_DESCRIPTOR_LEN;
+            let ie = payload_ies.next()?;
+            if ie.group_id != group_ids::MLME {
+                continue;
+            }
+
+            match ie.nested_ies() {
+                Ok(nested) => self.nested = Some((content_offset, nested.iter())),
+                Err(e) => return Some(Err(e.at(content_offset))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ie::header::HeaderTermination;
+
+    #[test]
+    fn without_ies() {
+        let ies = FrameIes::parse(&[0x07], false).unwrap();
+        assert_eq!(ies.header_ies, None);
+        assert_eq!(ies.payload, &[0x07]);
+        assert_eq!(ies.payload_offset(), 0);
+        assert_eq!(ies.mlme_ies().count(), 0);
+    }
+
+    #[test]
+    fn header_ies_only() {
+        // a Time Correction IE and HT2
+        let buf = [0x02, 0x0f, 0xff, 0x8f, 0x80, 0x3f, 0x07];
+        let ies = FrameIes::parse(&buf, true).unwrap();
+
+        let header_ies = ies.header_ies.unwrap();
+        assert_eq!(header_ies.termination(), HeaderTermination::Payload);
+        assert_eq!(header_ies.iter().count(), 1);
+        assert_eq!(ies.payload_ies, None);
+        assert_eq!(ies.payload, &[0x07]);
+        assert_eq!(ies.payload_offset(), 6);
+    }
+
+    #[test]
+    fn payload_ies() {
+        // HT1, an MLME IE holding two short nested IEs, PT and the MAC payload
+        let buf = [
+            0x00, 0x3f, 0x06, 0x88, 0x01, 0x1a, 0x07, 0x01, 0x1b, 0x08, 0x00, 0xf8, 0x07,
+        ];
+        let ies = FrameIes::parse(&buf, true).unwrap();
+
+        assert_eq!(ies.payload_ies.unwrap().iter().count(), 1);
+        assert_eq!(ies.payload, &[0x07]);
+        assert_eq!(ies.payload_offset(), 12);
+
+        let mut mlme_ies = ies.mlme_ies();
+        assert_eq!(
+            mlme_ies.next(),
+            Some(Ok((
+                4,
+                NestedIe::Short {
+                    sub_id: 0x1a,
+                    content: &[0x07],
+                }
+            )))
+        );
+        assert_eq!(
+            mlme_ies.next(),
+            Some(Ok((
+                7,
+                NestedIe::Short {
+                    sub_id: 0x1b,
+                    content: &[0x08],
+                }
+            )))
+        );
+        assert_eq!(mlme_ies.next(), None);
+
+        // payload IEs overrunning the IE section, and a nested IE overrunning its MLME IE
+        assert_eq!(
+            FrameIes::parse(&buf[..6], true),
+            Err(ParseError::IeOverrun { offset: 2 })
+        );
+        let mut overrun = buf;
+        overrun[7] = 0x02;
+        let ies = FrameIes::parse(&overrun, true).unwrap();
+        let mut mlme_ies = ies.mlme_ies();
+        assert_eq!(
+            mlme_ies.next(),
+            Some(Err(ParseError::IeOverrun { offset: 7 }))
+        );
+        assert_eq!(mlme_ies.next(), None);
+    }
+}
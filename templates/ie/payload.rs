@@ -0,0 +1,375 @@
+//! The list of payload IEs following a Header Termination 1 IE, and the nested IEs
+//! contained in MLME IEs.
+
+use core::convert::TryInto;
+
+use crate::error::{get, ParseError};
+
+use super::header::IE_DESCRIPTOR_LEN;
+
+pub use crate::layout::{NestedIeLongDescriptor, NestedIeShortDescriptor, PayloadIeDescriptor};
+
+/// The group IDs of payload IEs, see `Payload_IE_control`.
+pub mod group_ids {
+    pub const ESDU: u8 = 0x0;
+    pub const MLME: u8 = 0x1;
+    pub const VENDOR_SPECIFIC: u8 = 0x2;
+    pub const IETF: u8 = 0x5;
+    pub const PAYLOAD_TERMINATION: u8 = 0xf;
+}
+
+/// A payload IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PayloadIe<'a> {
+    pub group_id: u8,
+    pub content: &'a [u8],
+}
+
+impl<'a> PayloadIe<'a> {
+    /// The nested IEs of an MLME IE. Fails for other groups or if a nested IE overruns
+    /// the content, with the offset relative to the start of the content.
+    pub fn nested_ies(&self) -> Result<NestedIeList<'a>, ParseError> {
+        if self.group_id != group_ids::MLME {
+            return Err(ParseError::UnexpectedIe { offset: 0 });
+        }
+
+        NestedIeList::parse(self.content)
+    }
+}
+
+/// A validated list of payload IEs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PayloadIeList<'a> {
+    ies: &'a [u8],
+    terminated: bool,
+    rest: &'a [u8],
+}
+
+impl<'a> PayloadIeList<'a> {
+    /// Walks the payload IEs at the start of `buf` up to and including the Payload
+    /// Termination IE. Fails if an IE is a header IE or its content overruns `buf`.
+    pub fn parse(buf: &'a [u8]) -> Result<Self, ParseError> {
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let (group_id, len) = parse_descriptor(&buf[offset..]).map_err(|e| e.at(offset))?;
+            let end = offset + IE_DESCRIPTOR_LEN + len;
+            if end > buf.len() {
+                return Err(ParseError::IeOverrun { offset });
+            }
+
+            if group_id == group_ids::PAYLOAD_TERMINATION {
+                return Ok(PayloadIeList {
+                    ies: &buf[..offset],
+                    terminated: true,
+                    rest: &buf[end..],
+                });
+            }
+            offset = end;
+        }
+
+        Ok(PayloadIeList {
+            ies: buf,
+            terminated: false,
+            rest: &buf[buf.len()..],
+        })
+    }
+
+    /// The number of octets occupied by the list, including the termination IE.
+    pub fn buffer_len(&self) -> usize {
+        let termination_len = if self.terminated {
+            IE_DESCRIPTOR_LEN
+        } else {
+            0
+        };
+
+        self.ies.len() + termination_len
+    }
+
+    /// The payload IEs of the list, excluding the termination IE.
+    pub fn iter(&self) -> PayloadIes<'a> {
+        PayloadIes {
+            buf: self.ies,
+            offset: 0,
+        }
+    }
+
+    /// The MAC payload following the list.
+    pub fn payload(&self) -> &'a [u8] {
+        self.rest
+    }
+}
+
+impl<'a> IntoIterator for &PayloadIeList<'a> {
+    type Item = PayloadIe<'a>;
+    type IntoIter = PayloadIes<'a>;
+
+    fn into_iter(self) -> PayloadIes<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the IEs of a `PayloadIeList`.
+#[derive(Clone, Debug)]
+pub struct PayloadIes<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> PayloadIes<'a> {
+    /// The offset of the descriptor of the next IE relative to the start of the list.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> Iterator for PayloadIes<'a> {
+    type Item = PayloadIe<'a>;
+
+    fn next(&mut self) -> Option<PayloadIe<'a>> {
+        let (group_id, len) = parse_descriptor(self.buf).ok()?;
+        let end = IE_DESCRIPTOR_LEN + len;
+
+        let content = &self.buf[IE_DESCRIPTOR_LEN..end];
+        self.buf = &self.buf[end..];
+        self.offset += end;
+
+        Some(PayloadIe { group_id, content })
+    }
+}
+
+/// Encodes the descriptor of a payload IE with `group_id` and `len` octets of content.
+/// Returns `None` if the content is too long.
+pub fn payload_ie_descriptor(group_id: u8, len: usize) -> Option<[u8; IE_DESCRIPTOR_LEN]> {
+    if len > usize::from(PayloadIeDescriptor::LENGTH_MAX) {
+        return None;
+    }
+
+    let mut descriptor = PayloadIeDescriptor::default();
+    descriptor.set_length(len as u16);
+    descriptor.set_group_id(group_id);
+    descriptor.set_payload_ie(true);
+    Some(descriptor.0.to_le_bytes())
+}
+
+/// Decodes the group ID and content length of the payload IE descriptor at the start of
+/// `buf`.
+fn parse_descriptor(buf: &[u8]) -> Result<(u8, usize), ParseError> {
+    let descriptor = PayloadIeDescriptor(u16::from_le_bytes(
+        get(buf, 0, IE_DESCRIPTOR_LEN)?.try_into().unwrap(),
+    ));
+    if !descriptor.payload_ie() {
+        return Err(ParseError::UnexpectedIe { offset: 0 });
+    }
+
+    Ok((descriptor.group_id(), usize::from(descriptor.length())))
+}
+
+/// A nested IE of an MLME IE, see `Nested_IE_short_control` and
+/// `Nested_IE_long_control`. Short and long nested IEs use distinct sub-ID spaces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NestedIe<'a> {
+    Short { sub_id: u8, content: &'a [u8] },
+    Long { sub_id: u8, content: &'a [u8] },
+}
+
+impl<'a> NestedIe<'a> {
+    pub fn content(&self) -> &'a [u8] {
+        match self {
+            NestedIe::Short { content, .. } | NestedIe::Long { content, .. } => content,
+        }
+    }
+}
+
+/// A validated list of the nested IEs of an MLME IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NestedIeList<'a> {
+    ies: &'a [u8],
+}
+
+impl<'a> NestedIeList<'a> {
+    /// Checks that the nested IEs fill the `content` of an MLME IE exactly.
+    pub fn parse(content: &'a [u8]) -> Result<Self, ParseError> {
+        let mut offset = 0;
+        while offset < content.len() {
+            let (_, _, len) =
+                parse_nested_descriptor(&content[offset..]).map_err(|e| e.at(offset))?;
+            let end = offset + IE_DESCRIPTOR_LEN + len;
+            if end > content.len() {
+                return Err(ParseError::IeOverrun { offset });
+            }
+            offset = end;
+        }
+
+        Ok(NestedIeList { ies: content })
+    }
+
+    pub fn iter(&self) -> NestedIes<'a> {
+        NestedIes {
+            buf: self.ies,
+            offset: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &NestedIeList<'a> {
+    type Item = NestedIe<'a>;
+    type IntoIter = NestedIes<'a>;
+
+    fn into_iter(self) -> NestedIes<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the IEs of a `NestedIeList`.
+#[derive(Clone, Debug)]
+pub struct NestedIes<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> NestedIes<'a> {
+    /// The offset of the descriptor of the next IE relative to the start of the list.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> Iterator for NestedIes<'a> {
+    type Item = NestedIe<'a>;
+
+    fn next(&mut self) -> Option<NestedIe<'a>> {
+        let (long, sub_id, len) = parse_nested_descriptor(self.buf).ok()?;
+        let end = IE_DESCRIPTOR_LEN + len;
+
+        let content = &self.buf[IE_DESCRIPTOR_LEN..end];
+        self.buf = &self.buf[end..];
+        self.offset += end;
+
+        Some(if long {
+            NestedIe::Long { sub_id, content }
+        } else {
+            NestedIe::Short { sub_id, content }
+        })
+    }
+}
+
+/// Decodes the nested IE descriptor at the start of `buf`, returning if it is a long
+/// nested IE, its sub-ID and the length of its content.
+fn parse_nested_descriptor(buf: &[u8]) -> Result<(bool, u8, usize), ParseError> {
+    let descriptor = u16::from_le_bytes(get(buf, 0, IE_DESCRIPTOR_LEN)?.try_into().unwrap());
+
+    // the type is the most significant bit of both layouts
+    let short = NestedIeShortDescriptor(descriptor);
+    Ok(if !short.long() {
+        (false, short.sub_id(), usize::from(short.length()))
+    } else {
+        let long = NestedIeLongDescriptor(descriptor);
+        (true, long.sub_id(), usize::from(long.length()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an MLME IE holding a short and a long nested IE, an empty vendor specific IE and
+    // the Payload Termination IE, followed by the MAC payload
+    const IES: [u8; 14] = [
+        0x06, 0x88, 0x01, 0x1a, 0x07, 0x01, 0xc8, 0x05, 0x00, 0x90, 0x00, 0xf8, 0xaa, 0xbb,
+    ];
+
+    #[test]
+    fn descriptors() {
+        assert_eq!(
+            payload_ie_descriptor(group_ids::MLME, 6),
+            Some([0x06, 0x88])
+        );
+        assert_eq!(
+            payload_ie_descriptor(group_ids::PAYLOAD_TERMINATION, 0),
+            Some([0x00, 0xf8])
+        );
+        assert_eq!(payload_ie_descriptor(group_ids::MLME, 0x800), None);
+
+        assert_eq!(parse_nested_descriptor(&[0x01, 0x1a]), Ok((false, 0x1a, 1)));
+        assert_eq!(
+            parse_nested_descriptor(&[0xff, 0x7f]),
+            Ok((false, 0x7f, 0xff))
+        );
+        assert_eq!(parse_nested_descriptor(&[0x01, 0xc8]), Ok((true, 0x9, 1)));
+        assert_eq!(
+            parse_nested_descriptor(&[0xff, 0xff]),
+            Ok((true, 0xf, 0x7ff))
+        );
+    }
+
+    #[test]
+    fn payload_ies() {
+        let ies = PayloadIeList::parse(&IES).unwrap();
+        assert_eq!(ies.buffer_len(), 12);
+        assert_eq!(ies.payload(), &[0xaa, 0xbb]);
+
+        let mut iter = ies.iter();
+        let mlme = iter.next().unwrap();
+        assert_eq!(mlme.group_id, group_ids::MLME);
+        assert_eq!(iter.offset(), 8);
+        let vendor = iter.next().unwrap();
+        assert_eq!(vendor.group_id, group_ids::VENDOR_SPECIFIC);
+        assert_eq!(vendor.content, &[]);
+        assert_eq!(iter.next(), None);
+
+        let nested = mlme.nested_ies().unwrap();
+        let mut iter = nested.iter();
+        assert_eq!(
+            iter.next(),
+            Some(NestedIe::Short {
+                sub_id: 0x1a,
+                content: &[0x07],
+            })
+        );
+        assert_eq!(iter.offset(), 3);
+        assert_eq!(
+            iter.next(),
+            Some(NestedIe::Long {
+                sub_id: 0x9,
+                content: &[0x05],
+            })
+        );
+        assert_eq!(iter.next(), None);
+        assert_eq!(
+            vendor.nested_ies(),
+            Err(ParseError::UnexpectedIe { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn unterminated() {
+        let ies = PayloadIeList::parse(&IES[..10]).unwrap();
+        assert_eq!(ies.iter().count(), 2);
+        assert_eq!(ies.buffer_len(), 10);
+        assert_eq!(ies.payload(), &[]);
+    }
+
+    #[test]
+    fn malformed() {
+        // a header IE in place of the Payload Termination IE
+        let mut buf = IES;
+        buf[10..12].copy_from_slice(&[0x00, 0x3f]);
+        assert_eq!(
+            PayloadIeList::parse(&buf),
+            Err(ParseError::UnexpectedIe { offset: 10 })
+        );
+        assert_eq!(
+            PayloadIeList::parse(&IES[..7]),
+            Err(ParseError::IeOverrun { offset: 0 })
+        );
+        assert_eq!(
+            NestedIeList::parse(&[0x01, 0x1a, 0x07, 0x02, 0xc8, 0x05]),
+            Err(ParseError::IeOverrun { offset: 3 })
+        );
+        assert_eq!(
+            NestedIeList::parse(&[0x01, 0x1a, 0x07, 0x02]),
+            Err(ParseError::Truncated { offset: 3 })
+        );
+    }
+}
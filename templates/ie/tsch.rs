@@ -0,0 +1,544 @@
+//! The contents of the TSCH nested IEs carried in MLME IEs.
+
+use core::convert::TryInto;
+
+use crate::error::{get, ParseError};
+
+use super::payload::NestedIe;
+
+/// The sub-IDs of the short nested IEs used by TSCH.
+pub mod short_sub_ids {
+    pub const TSCH_SYNCHRONIZATION: u8 = 0x1a;
+    pub const TSCH_SLOTFRAME_AND_LINK: u8 = 0x1b;
+    pub const TSCH_TIMESLOT: u8 = 0x1c;
+}
+
+/// The sub-IDs of the long nested IEs used by TSCH.
+pub mod long_sub_ids {
+    pub const CHANNEL_HOPPING: u8 = 0x9;
+}
+
+/// The content of the TSCH Synchronization IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TschSynchronizationIe {
+    /// The 5-octet absolute slot number.
+    pub asn: u64,
+    pub join_metric: u8,
+}
+
+impl TschSynchronizationIe {
+    pub const CONTENT_LEN: usize = 6;
+
+    fn parse(content: &[u8]) -> Result<Self, ParseError> {
+        if content.len() != Self::CONTENT_LEN {
+            return Err(ParseError::InvalidField { offset: 0 });
+        }
+
+        let mut asn = [0; 8];
+        asn[..5].copy_from_slice(&content[..5]);
+
+        Ok(TschSynchronizationIe {
+            asn: u64::from_le_bytes(asn),
+            join_metric: content[5],
+        })
+    }
+
+    /// Encodes the content of the IE. Only the lower 40 bits of the ASN are encoded.
+    pub fn to_bytes(&self) -> [u8; Self::CONTENT_LEN] {
+        let mut content = [0; Self::CONTENT_LEN];
+        content[..5].copy_from_slice(&self.asn.to_le_bytes()[..5]);
+        content[5] = self.join_metric;
+        content
+    }
+}
+
+/// The link options of a TSCH link.
+pub mod link_options {
+    pub const TX: u8 = 0x01;
+    pub const RX: u8 = 0x02;
+    pub const SHARED: u8 = 0x04;
+    pub const TIMEKEEPING: u8 = 0x08;
+    pub const PRIORITY: u8 = 0x10;
+}
+
+/// A link of a slotframe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Link {
+    pub timeslot: u16,
+    pub channel_offset: u16,
+    /// A combination of the `link_options`.
+    pub link_options: u8,
+}
+
+const SLOTFRAME_HEADER_LEN: usize = 4;
+const LINK_LEN: usize = 5;
+
+/// A slotframe of the TSCH Slotframe and Link IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Slotframe<'a> {
+    pub handle: u8,
+    pub size: u16,
+    links: &'a [u8],
+}
+
+impl<'a> Slotframe<'a> {
+    pub fn links(&self) -> impl Iterator<Item = Link> + 'a {
+        self.links.chunks_exact(LINK_LEN).map(|l| Link {
+            timeslot: u16::from_le_bytes([l[0], l[1]]),
+            channel_offset: u16::from_le_bytes([l[2], l[3]]),
+            link_options: l[4],
+        })
+    }
+}
+
+/// The content of the TSCH Slotframe and Link IE, a list of slotframes each carrying a
+/// list of links.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TschSlotframeAndLinkIe<'a> {
+    pub number_of_slotframes: u8,
+    slotframes: &'a [u8],
+}
+
+impl<'a> TschSlotframeAndLinkIe<'a> {
+    /// Checks that the slotframes and their links fill `content` exactly.
+    fn parse(content: &'a [u8]) -> Result<Self, ParseError> {
+        let number_of_slotframes = get(content, 0, 1)?[0];
+
+        let mut offset = 1;
+        for _ in 0..number_of_slotframes {
+            let number_of_links =
+                get(content, offset, offset + SLOTFRAME_HEADER_LEN)?[SLOTFRAME_HEADER_LEN - 1];
+            let end = offset + SLOTFRAME_HEADER_LEN + usize::from(number_of_links) * LINK_LEN;
+            if end > content.len() {
+                return Err(ParseError::Truncated { offset });
+            }
+            offset = end;
+        }
+
+        if offset != content.len() {
+            return Err(ParseError::InvalidField { offset });
+        }
+
+        Ok(TschSlotframeAndLinkIe {
+            number_of_slotframes,
+            slotframes: &content[1..],
+        })
+    }
+
+    pub fn slotframes(&self) -> Slotframes<'a> {
+        Slotframes {
+            buf: self.slotframes,
+        }
+    }
+}
+
+/// An iterator over the slotframes of a `TschSlotframeAndLinkIe`.
+#[derive(Clone, Debug)]
+pub struct Slotframes<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Iterator for Slotframes<'a> {
+    type Item = Slotframe<'a>;
+
+    fn next(&mut self) -> Option<Slotframe<'a>> {
+        let header = self.buf.get(..SLOTFRAME_HEADER_LEN)?;
+        let end = SLOTFRAME_HEADER_LEN + usize::from(header[3]) * LINK_LEN;
+
+        let slotframe = Slotframe {
+            handle: header[0],
+            size: u16::from_le_bytes([header[1], header[2]]),
+            links: &self.buf[SLOTFRAME_HEADER_LEN..end],
+        };
+        self.buf = &self.buf[end..];
+
+        Some(slotframe)
+    }
+}
+
+/// The timeslot timings of a TSCH Timeslot IE, in microseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimeslotTemplate {
+    pub cca_offset: u16,
+    pub cca: u16,
+    pub tx_offset: u16,
+    pub rx_offset: u16,
+    pub rx_ack_delay: u16,
+    pub tx_ack_delay: u16,
+    pub rx_wait: u16,
+    pub ack_wait: u16,
+    pub rx_tx: u16,
+    pub max_ack: u16,
+    /// Encoded using 3 octets if it or `timeslot_length` exceeds 16 bits.
+    pub max_tx: u32,
+    pub timeslot_length: u32,
+}
+
+/// The content of the TSCH Timeslot IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TschTimeslotIe {
+    pub timeslot_id: u8,
+    /// Only included if the timeslot template is not the default template.
+    pub template: Option<TimeslotTemplate>,
+}
+
+impl TschTimeslotIe {
+    const SHORT_TEMPLATE_LEN: usize = 24;
+    const LONG_TEMPLATE_LEN: usize = 26;
+
+    fn parse(content: &[u8]) -> Result<Self, ParseError> {
+        let timeslot_id = get(content, 0, 1)?[0];
+        let template = &content[1..];
+
+        let wide = match template.len() {
+            0 => {
+                return Ok(TschTimeslotIe {
+                    timeslot_id,
+                    template: None,
+                })
+            }
+            Self::SHORT_TEMPLATE_LEN => false,
+            Self::LONG_TEMPLATE_LEN => true,
+            _ => return Err(ParseError::InvalidField { offset: 1 }),
+        };
+
+        let u16_at = |i: usize| u16::from_le_bytes([template[2 * i], template[2 * i + 1]]);
+        let tail = &template[20..];
+        let (max_tx, timeslot_length) = if wide {
+            (
+                u32::from_le_bytes([tail[0], tail[1], tail[2], 0]),
+                u32::from_le_bytes([tail[3], tail[4], tail[5], 0]),
+            )
+        } else {
+            (
+                u32::from(u16::from_le_bytes([tail[0], tail[1]])),
+                u32::from(u16::from_le_bytes([tail[2], tail[3]])),
+            )
+        };
+
+        Ok(TschTimeslotIe {
+            timeslot_id,
+            template: Some(TimeslotTemplate {
+                cca_offset: u16_at(0),
+                cca: u16_at(1),
+                tx_offset: u16_at(2),
+                rx_offset: u16_at(3),
+                rx_ack_delay: u16_at(4),
+                tx_ack_delay: u16_at(5),
+                rx_wait: u16_at(6),
+                ack_wait: u16_at(7),
+                rx_tx: u16_at(8),
+                max_ack: u16_at(9),
+                max_tx,
+                timeslot_length,
+            }),
+        })
+    }
+}
+
+/// The hopping sequence carried in the full form of the Channel Hopping IE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HoppingSequence<'a> {
+    pub channel_page: u8,
+    pub number_of_channels: u16,
+    pub phy_configuration: u32,
+    list: &'a [u8],
+    pub current_hop: u16,
+}
+
+impl<'a> HoppingSequence<'a> {
+    /// The channels of the hopping sequence list.
+    pub fn channels(&self) -> impl Iterator<Item = u16> + 'a {
+        self.list
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+    }
+}
+
+/// The content of the Channel Hopping IE. Enhanced beacons typically only carry the
+/// hopping sequence ID. The extended bitmap used by SUN PHYs is not decoded, such
+/// IEs carry the raw content following the hopping sequence ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelHoppingIe<'a> {
+    SequenceId(u8),
+    Full(u8, HoppingSequence<'a>),
+    Raw(u8, &'a [u8]),
+}
+
+impl<'a> ChannelHoppingIe<'a> {
+    /// The length of the channel page, number of channels and PHY configuration.
+    const SEQUENCE_HEADER_LEN: usize = 7;
+
+    fn parse(content: &'a [u8]) -> Result<Self, ParseError> {
+        let id = get(content, 0, 1)?[0];
+        let rest = &content[1..];
+        if rest.is_empty() {
+            return Ok(ChannelHoppingIe::SequenceId(id));
+        }
+
+        // assumes an empty extended bitmap
+        let full = rest.get(Self::SEQUENCE_HEADER_LEN..).and_then(|list| {
+            let len = usize::from(u16::from_le_bytes(list.get(..2)?.try_into().ok()?));
+            if list.len() != 2 + 2 * len + 2 {
+                return None;
+            }
+
+            Some(HoppingSequence {
+                channel_page: rest[0],
+                number_of_channels: u16::from_le_bytes([rest[1], rest[2]]),
+                phy_configuration: u32::from_le_bytes(rest[3..7].try_into().ok()?),
+                list: &list[2..2 + 2 * len],
+                current_hop: u16::from_le_bytes(list[2 + 2 * len..].try_into().ok()?),
+            })
+        });
+
+        Ok(match full {
+            Some(sequence) => ChannelHoppingIe::Full(id, sequence),
+            None => ChannelHoppingIe::Raw(id, rest),
+        })
+    }
+}
+
+/// The typed content of a nested IE used by TSCH.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TschIeContent<'a> {
+    Synchronization(TschSynchronizationIe),
+    SlotframeAndLink(TschSlotframeAndLinkIe<'a>),
+    Timeslot(TschTimeslotIe),
+    ChannelHopping(ChannelHoppingIe<'a>),
+    /// A nested IE which is not used by TSCH.
+    Other(NestedIe<'a>),
+}
+
+impl<'a> TschIeContent<'a> {
+    /// Decodes the content of `ie` according to its sub-ID. Fails if the content does
+    /// not match the layout of the IE, with the offset relative to the start of the
+    /// content.
+    pub fn parse(ie: &NestedIe<'a>) -> Result<Self, ParseError> {
+        Ok(match *ie {
+            NestedIe::Short {
+                sub_id: short_sub_ids::TSCH_SYNCHRONIZATION,
+                content,
+            } => TschIeContent::Synchronization(TschSynchronizationIe::parse(content)?),
+            NestedIe::Short {
+                sub_id: short_sub_ids::TSCH_SLOTFRAME_AND_LINK,
+                content,
+            } => TschIeContent::SlotframeAndLink(TschSlotframeAndLinkIe::parse(content)?),
+            NestedIe::Short {
+                sub_id: short_sub_ids::TSCH_TIMESLOT,
+                content,
+            } => TschIeContent::Timeslot(TschTimeslotIe::parse(content)?),
+            NestedIe::Long {
+                sub_id: long_sub_ids::CHANNEL_HOPPING,
+                content,
+            } => TschIeContent::ChannelHopping(ChannelHoppingIe::parse(content)?),
+            other => TschIeContent::Other(other),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn short(sub_id: u8, content: &[u8]) -> Result<TschIeContent<'_>, ParseError> {
+        TschIeContent::parse(&NestedIe::Short { sub_id, content })
+    }
+
+    fn long(sub_id: u8, content: &[u8]) -> Result<TschIeContent<'_>, ParseError> {
+        TschIeContent::parse(&NestedIe::Long { sub_id, content })
+    }
+
+    #[test]
+    fn synchronization() {
+        let content = [0x01, 0x02, 0x03, 0x04, 0x05, 0x07];
+        let ie = TschSynchronizationIe {
+            asn: 0x05_0403_0201,
+            join_metric: 7,
+        };
+
+        assert_eq!(
+            short(short_sub_ids::TSCH_SYNCHRONIZATION, &content),
+            Ok(TschIeContent::Synchronization(ie))
+        );
+        assert_eq!(ie.to_bytes(), content);
+        assert_eq!(
+            TschSynchronizationIe {
+                asn: 0xff_0000_0000_0000 | ie.asn,
+                ..ie
+            }
+            .to_bytes(),
+            content
+        );
+        assert_eq!(
+            short(short_sub_ids::TSCH_SYNCHRONIZATION, &content[..5]),
+            Err(ParseError::InvalidField { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn slotframe_and_link() {
+        let content = [
+            0x02, // number of slotframes
+            0x00, 0x65, 0x00, 0x02, // handle 0, size 101, 2 links
+            0x00, 0x00, 0x00, 0x00, 0x0f, // timeslot 0, channel offset 0
+            0x01, 0x00, 0x02, 0x00, 0x02, // timeslot 1, channel offset 2
+            0x01, 0x07, 0x00, 0x00, // handle 1, size 7, no links
+        ];
+
+        let ie = match short(short_sub_ids::TSCH_SLOTFRAME_AND_LINK, &content) {
+            Ok(TschIeContent::SlotframeAndLink(ie)) => ie,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(ie.number_of_slotframes, 2);
+
+        let mut slotframes = ie.slotframes();
+        let slotframe = slotframes.next().unwrap();
+        assert_eq!((slotframe.handle, slotframe.size), (0, 101));
+        let mut links = slotframe.links();
+        assert_eq!(
+            links.next(),
+            Some(Link {
+                timeslot: 0,
+                channel_offset: 0,
+                link_options: link_options::TX
+                    | link_options::RX
+                    | link_options::SHARED
+                    | link_options::TIMEKEEPING,
+            })
+        );
+        assert_eq!(
+            links.next(),
+            Some(Link {
+                timeslot: 1,
+                channel_offset: 2,
+                link_options: link_options::RX,
+            })
+        );
+        assert_eq!(links.next(), None);
+
+        let slotframe = slotframes.next().unwrap();
+        assert_eq!((slotframe.handle, slotframe.size), (1, 7));
+        assert_eq!(slotframe.links().count(), 0);
+        assert_eq!(slotframes.next(), None);
+
+        // the last slotframe is missing and the links do not fill the content
+        assert_eq!(
+            short(short_sub_ids::TSCH_SLOTFRAME_AND_LINK, &content[..15]),
+            Err(ParseError::Truncated { offset: 15 })
+        );
+        assert_eq!(
+            short(short_sub_ids::TSCH_SLOTFRAME_AND_LINK, &content[..12]),
+            Err(ParseError::Truncated { offset: 1 })
+        );
+        let mut trailing = [0; 20];
+        trailing[..19].copy_from_slice(&content);
+        assert_eq!(
+            short(short_sub_ids::TSCH_SLOTFRAME_AND_LINK, &trailing),
+            Err(ParseError::InvalidField { offset: 19 })
+        );
+    }
+
+    #[test]
+    fn timeslot() {
+        assert_eq!(
+            short(short_sub_ids::TSCH_TIMESLOT, &[0x00]),
+            Ok(TschIeContent::Timeslot(TschTimeslotIe {
+                timeslot_id: 0,
+                template: None,
+            }))
+        );
+
+        let mut content = [0; 27];
+        content[0] = 1;
+        for i in 0..10 {
+            content[1 + 2 * i] = i as u8 + 1;
+        }
+        let template = TimeslotTemplate {
+            cca_offset: 1,
+            cca: 2,
+            tx_offset: 3,
+            rx_offset: 4,
+            rx_ack_delay: 5,
+            tx_ack_delay: 6,
+            rx_wait: 7,
+            ack_wait: 8,
+            rx_tx: 9,
+            max_ack: 10,
+            max_tx: 4256,
+            timeslot_length: 10000,
+        };
+
+        // 2-octet max TX and timeslot length
+        content[21..25].copy_from_slice(&[0xa0, 0x10, 0x10, 0x27]);
+        assert_eq!(
+            short(short_sub_ids::TSCH_TIMESLOT, &content[..25]),
+            Ok(TschIeContent::Timeslot(TschTimeslotIe {
+                timeslot_id: 1,
+                template: Some(template),
+            }))
+        );
+
+        // 3-octet max TX and timeslot length
+        content[21..27].copy_from_slice(&[0xa0, 0x10, 0x00, 0xa0, 0x86, 0x01]);
+        assert_eq!(
+            short(short_sub_ids::TSCH_TIMESLOT, &content),
+            Ok(TschIeContent::Timeslot(TschTimeslotIe {
+                timeslot_id: 1,
+                template: Some(TimeslotTemplate {
+                    timeslot_length: 100_000,
+                    ..template
+                }),
+            }))
+        );
+
+        assert_eq!(
+            short(short_sub_ids::TSCH_TIMESLOT, &content[..26]),
+            Err(ParseError::InvalidField { offset: 1 })
+        );
+    }
+
+    #[test]
+    fn channel_hopping() {
+        assert_eq!(
+            long(long_sub_ids::CHANNEL_HOPPING, &[0x05]),
+            Ok(TschIeContent::ChannelHopping(ChannelHoppingIe::SequenceId(
+                5
+            )))
+        );
+
+        let content = [
+            0x00, // hopping sequence ID
+            0x00, 0x10, 0x00, // channel page 0, 16 channels
+            0x00, 0xf8, 0xff, 0x07, // PHY configuration
+            0x02, 0x00, 0x0b, 0x00, 0x1a, 0x00, // channels 11 and 26
+            0x01, 0x00, // current hop
+        ];
+        let sequence = match long(long_sub_ids::CHANNEL_HOPPING, &content) {
+            Ok(TschIeContent::ChannelHopping(ChannelHoppingIe::Full(0, sequence))) => sequence,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(sequence.channel_page, 0);
+        assert_eq!(sequence.number_of_channels, 16);
+        assert_eq!(sequence.phy_configuration, 0x07ff_f800);
+        assert!(sequence.channels().eq([11, 26].iter().cloned()));
+        assert_eq!(sequence.current_hop, 1);
+
+        // an extended bitmap or a length mismatch leaves the content undecoded
+        assert_eq!(
+            long(long_sub_ids::CHANNEL_HOPPING, &content[..15]),
+            Ok(TschIeContent::ChannelHopping(ChannelHoppingIe::Raw(
+                0,
+                &content[1..15]
+            )))
+        );
+
+        assert_eq!(
+            short(0x10, &[0x01]),
+            Ok(TschIeContent::Other(NestedIe::Short {
+                sub_id: 0x10,
+                content: &[0x01],
+            }))
+        );
+    }
+}
@@ -0,0 +1,14 @@
+//! The bitfields decoded by the hand written modules, rendered from the declarations of
+//! the bitfields with an accessor for each field.
+
+/// The `bits` wide field at `offset` of `value`.
+fn get(value: u32, offset: u32, bits: u32) -> u32 {
+    (value >> offset) & ((1 << bits) - 1)
+}
+
+/// Replaces the `bits` wide field at `offset` of `value` with `field`, dropping the bits
+/// of `field` which do not fit.
+fn set(value: u32, offset: u32, bits: u32, field: u32) -> u32 {
+    let mask = ((1 << bits) - 1) << offset;
+    (value & !mask) | ((field << offset) & mask)
+}
@@ -0,0 +1,209 @@
+//! The Enhanced Beacon Request command, a Beacon Request command of the current frame
+//! version optionally carrying an EB Filter IE.
+
+use crate::error::{get, ParseError};
+use crate::fcs::FCS_LEN;
+use crate::header::{frame_type, frame_version, Header};
+use crate::ie::{FrameIes, NestedIe, IE_DESCRIPTOR_LEN};
+use crate::layout::EbFilterDescriptor;
+
+use super::frame::MacCommand;
+
+/// The sub-ID of the EB Filter IE, a short nested IE.
+pub const EB_FILTER_SUB_ID: u8 = 0x1e;
+
+/// The content of the EB Filter IE. Only devices passing the filter respond to the
+/// request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EbFilterIe<'a> {
+    /// Only devices permitting association respond if set.
+    pub permit_joining_on: bool,
+    /// The minimum link quality of the request for a device to respond.
+    pub link_quality: Option<u8>,
+    /// The probability in percent with which a device responds.
+    pub percent_filter: Option<u8>,
+    /// The PIB attribute IDs to be included in the Enhanced Beacon, at most 3.
+    pub attribute_ids: &'a [u8],
+}
+
+impl<'a> EbFilterIe<'a> {
+    /// Decodes the content of an EB Filter IE, starting with the EB filter descriptor.
+    /// Fails if the content does not end with the number of PIB attribute IDs given by
+    /// the descriptor.
+    pub fn parse(content: &'a [u8]) -> Result<Self, ParseError> {
+        let descriptor = EbFilterDescriptor(get(content, 0, 1)?[0]);
+        let mut offset = 1;
+
+        let mut optional = |included: bool| -> Result<Option<u8>, ParseError> {
+            if !included {
+                return Ok(None);
+            }
+            let value = get(content, offset, offset + 1)?[0];
+            offset += 1;
+            Ok(Some(value))
+        };
+
+        let link_quality = optional(descriptor.include_link_quality())?;
+        let percent_filter = optional(descriptor.include_percent_filter())?;
+
+        let end = offset + usize::from(descriptor.attribute_id_count());
+        let attribute_ids = get(content, offset, end)?;
+        if content.len() != end {
+            return Err(ParseError::InvalidField { offset: end });
+        }
+
+        Ok(EbFilterIe {
+            permit_joining_on: descriptor.permit_joining_on(),
+            link_quality,
+            percent_filter,
+            attribute_ids,
+        })
+    }
+}
+
+/// A decoded Enhanced Beacon Request command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnhancedBeaconRequest<'a> {
+    pub header: Header,
+    pub ies: FrameIes<'a>,
+    pub eb_filter: Option<EbFilterIe<'a>>,
+}
+
+impl<'a> EnhancedBeaconRequest<'a> {
+    /// Decodes the Enhanced Beacon Request in `buf`, which includes the FCS. Fails if
+    /// the frame is not an unsecured Beacon Request command of the current frame
+    /// version, or its IEs are malformed.
+    pub fn parse(buf: &'a [u8]) -> Result<Self, ParseError> {
+        let (header, offset) = Header::parse(buf)?;
+        let frame_control = header.frame_control;
+        if frame_control.frame_type() != frame_type::MAC_COMMAND
+            || frame_control.frame_version() != frame_version::CURRENT
+            || frame_control.security_enabled()
+        {
+            return Err(ParseError::UnexpectedFrame { offset: 0 });
+        }
+
+        let body_end = buf
+            .len()
+            .checked_sub(FCS_LEN)
+            .filter(|&end| end >= offset)
+            .ok_or(ParseError::Truncated { offset })?;
+        let ies = FrameIes::parse(&buf[offset..body_end], frame_control.ie_present())
+            .map_err(|e| e.at(offset))?;
+
+        let payload_offset = offset + ies.payload_offset();
+        let command = MacCommand::parse(ies.payload, frame_control.frame_version())
+            .map_err(|e| e.at(payload_offset))?;
+        if command != MacCommand::BeaconRequest {
+            return Err(ParseError::UnexpectedFrame {
+                offset: payload_offset,
+            });
+        }
+
+        let mut eb_filter = None;
+        for ie in ies.mlme_ies() {
+            let (ie_offset, ie) = ie.map_err(|e| e.at(offset))?;
+            if let NestedIe::Short {
+                sub_id: EB_FILTER_SUB_ID,
+                content,
+            } = ie
+            {
+                let content_offset = offset + ie_offset + IE_DESCRIPTOR_LEN;
+                eb_filter = Some(EbFilterIe::parse(content).map_err(|e| e.at(content_offset))?);
+                break;
+            }
+        }
+
+        Ok(EnhancedBeaconRequest {
+            header,
+            ies,
+            eb_filter,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The MHR of a broadcast Enhanced Beacon Request with the sequence number
+    /// suppressed and IEs present.
+    const MHR: [u8; 6] = [0x03, 0x2b, 0xff, 0xff, 0xff, 0xff];
+
+    fn frame(body: &[u8]) -> ([u8; 32], usize) {
+        let mut buf = [0; 32];
+        buf[..MHR.len()].copy_from_slice(&MHR);
+        buf[MHR.len()..MHR.len() + body.len()].copy_from_slice(body);
+        (buf, MHR.len() + body.len() + FCS_LEN)
+    }
+
+    #[test]
+    fn eb_filter() {
+        // HT1, an MLME IE holding an EB Filter IE with link quality, PT and the command
+        let (buf, len) = frame(&[
+            0x00, 0x3f, 0x04, 0x88, 0x02, 0x1e, 0x03, 0x80, 0x00, 0xf8, 0x07,
+        ]);
+        let request = EnhancedBeaconRequest::parse(&buf[..len]).unwrap();
+
+        assert_eq!(request.ies.payload_offset(), 10);
+        assert_eq!(
+            request.eb_filter,
+            Some(EbFilterIe {
+                permit_joining_on: true,
+                link_quality: Some(0x80),
+                percent_filter: None,
+                attribute_ids: &[],
+            })
+        );
+    }
+
+    #[test]
+    fn eb_filter_attribute_ids() {
+        // a percent filter and 2 PIB attribute IDs
+        let content = [0x14, 0x32, 0x10, 0x20, 0x30];
+
+        assert_eq!(
+            EbFilterIe::parse(&content[..4]),
+            Ok(EbFilterIe {
+                permit_joining_on: false,
+                link_quality: None,
+                percent_filter: Some(50),
+                attribute_ids: &[0x10, 0x20],
+            })
+        );
+        assert_eq!(
+            EbFilterIe::parse(&content[..3]),
+            Err(ParseError::Truncated { offset: 2 })
+        );
+        assert_eq!(
+            EbFilterIe::parse(&content),
+            Err(ParseError::InvalidField { offset: 4 })
+        );
+        assert_eq!(
+            EbFilterIe::parse(&[0x02]),
+            Err(ParseError::Truncated { offset: 1 })
+        );
+    }
+
+    #[test]
+    fn unterminated_header_ies() {
+        // a header IE without termination takes the whole body, leaving no command
+        let (buf, len) = frame(&[0x00, 0x0d]);
+
+        assert_eq!(
+            EnhancedBeaconRequest::parse(&buf[..len]),
+            Err(ParseError::Truncated { offset: 8 })
+        );
+    }
+
+    #[test]
+    fn eb_filter_overrun() {
+        // the EB Filter IE claims 3 octets of the 1 left in its MLME IE
+        let (buf, len) = frame(&[0x00, 0x3f, 0x03, 0x88, 0x03, 0x1e, 0x03, 0x00, 0xf8, 0x07]);
+
+        assert_eq!(
+            EnhancedBeaconRequest::parse(&buf[..len]),
+            Err(ParseError::IeOverrun { offset: 10 })
+        );
+    }
+}
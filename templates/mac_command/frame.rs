@@ -0,0 +1,9 @@
+//! MAC command frame payloads, dispatched on the command identifier. The commands are
+//! rendered from the declarations of their payloads in `commands`, with bitfield valued
+//! fields decoded through the layouts of the bitfields.
+
+use core::convert::TryInto;
+
+use crate::error::{get, ParseError};
+use crate::header::frame_version;
+use crate::layout::{CapabilityInformation, GtsCharacteristics};
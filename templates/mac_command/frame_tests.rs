@@ -0,0 +1,148 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_all(buf: &[u8]) -> MacCommand<'_> {
+        let command = MacCommand::parse(buf, frame_version::CURRENT).unwrap();
+        assert_eq!(command.command_id(), buf[0]);
+        assert_eq!(command.buffer_len(), buf.len());
+        command
+    }
+
+    #[test]
+    fn assoc_request() {
+        // an FFD requesting a short address
+        let mut capability = CapabilityInformation::default();
+        capability.set_ffd(true);
+        capability.set_allocate_address(true);
+
+        assert_eq!(
+            parse_all(&[0x01, 0x82]),
+            MacCommand::AssocRequest { capability }
+        );
+    }
+
+    #[test]
+    fn assoc_response() {
+        let command = parse_all(&[0x02, 0x34, 0x12, 0x01]);
+
+        assert_eq!(
+            command,
+            MacCommand::AssocResponse {
+                short_address: 0x1234,
+                status: association_status::PAN_AT_CAPACITY,
+            }
+        );
+    }
+
+    #[test]
+    fn disassoc_notify() {
+        assert_eq!(
+            parse_all(&[0x03, 0x01]),
+            MacCommand::DisassocNotify {
+                reason: disassociation_reason::COORDINATOR_LEAVE,
+            }
+        );
+    }
+
+    #[test]
+    fn empty_payloads() {
+        assert_eq!(parse_all(&[0x04]), MacCommand::DataRequest);
+        assert_eq!(parse_all(&[0x05]), MacCommand::PanIdConflict);
+        assert_eq!(parse_all(&[0x06]), MacCommand::OrphanNotify);
+        assert_eq!(parse_all(&[0x07]), MacCommand::BeaconRequest);
+    }
+
+    fn coordinator_realign(channel_page: Option<u8>) -> MacCommand<'static> {
+        MacCommand::CoordinatorRealign {
+            pan_id: 0xabcd,
+            coordinator_short_address: 0x0000,
+            channel_number: 11,
+            short_address: 0x1234,
+            channel_page,
+        }
+    }
+
+    #[test]
+    fn channel_page() {
+        let buf = [0x08, 0xcd, 0xab, 0x00, 0x00, 0x0b, 0x34, 0x12, 0x02];
+
+        assert_eq!(parse_all(&buf), coordinator_realign(Some(2)));
+        assert_eq!(
+            MacCommand::parse(&buf, frame_version::VERSION_2006),
+            Ok(coordinator_realign(Some(2)))
+        );
+
+        // the channel page may be omitted since frame version 2006
+        assert_eq!(parse_all(&buf[..8]), coordinator_realign(None));
+        assert_eq!(
+            MacCommand::parse(&buf[..8], frame_version::VERSION_2003),
+            Ok(coordinator_realign(None))
+        );
+
+        // frames of version 2003 never include the channel page
+        assert_eq!(
+            MacCommand::parse(&buf, frame_version::VERSION_2003),
+            Err(ParseError::InvalidField { offset: 8 })
+        );
+    }
+
+    #[test]
+    fn gts_request() {
+        // allocate a receive GTS of 2 slots
+        let command = parse_all(&[0x09, 0x32]);
+
+        match command {
+            MacCommand::GtsRequest { characteristics } => {
+                assert_eq!(characteristics.gts_length(), 2);
+                assert!(characteristics.receive_only());
+                assert!(characteristics.allocation());
+            }
+            _ => panic!("unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(
+            parse_all(&[0x20, 0x01, 0x02]),
+            MacCommand::Unknown(0x20, &[0x01, 0x02])
+        );
+        assert_eq!(parse_all(&[0x20]), MacCommand::Unknown(0x20, &[]));
+    }
+
+    #[test]
+    fn wrong_length() {
+        assert_eq!(
+            MacCommand::parse(&[0x02, 0x34, 0x12], frame_version::CURRENT),
+            Err(ParseError::Truncated { offset: 3 })
+        );
+        assert_eq!(
+            MacCommand::parse(
+                &[0x08, 0xcd, 0xab, 0x00, 0x00, 0x0b],
+                frame_version::CURRENT
+            ),
+            Err(ParseError::Truncated { offset: 6 })
+        );
+        assert_eq!(
+            MacCommand::parse(&[0x03, 0x02, 0x00], frame_version::CURRENT),
+            Err(ParseError::InvalidField { offset: 2 })
+        );
+        assert_eq!(
+            MacCommand::parse(&[0x04, 0x00], frame_version::CURRENT),
+            Err(ParseError::InvalidField { offset: 1 })
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(
+            MacCommand::parse(&[], frame_version::CURRENT),
+            Err(ParseError::Truncated { offset: 0 })
+        );
+        assert_eq!(
+            MacCommand::parse(&[0x01], frame_version::CURRENT),
+            Err(ParseError::Truncated { offset: 1 })
+        );
+    }
+}
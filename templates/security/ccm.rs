@@ -0,0 +1,171 @@
+//! The CCM* mode of operation with AES-128 and a 2-octet length field, as used to
+//! secure frames.
+
+use super::aes::{Aes128, Block, BLOCK_LEN};
+
+/// The length of a key.
+pub const KEY_LEN: usize = 16;
+/// The length of the nonce.
+pub const NONCE_LEN: usize = 13;
+
+pub type Key = [u8; KEY_LEN];
+pub type Nonce = [u8; NONCE_LEN];
+
+/// The size of the length field, L.
+const LENGTH_LEN: usize = 2;
+
+/// Builds the nonce from the extended address of the originator, the frame counter and
+/// the security level of the frame.
+pub fn nonce(source: u64, frame_counter: u32, security_level: u8) -> Nonce {
+    let mut nonce = [0; NONCE_LEN];
+    nonce[..8].copy_from_slice(&source.to_be_bytes());
+    nonce[8..12].copy_from_slice(&frame_counter.to_be_bytes());
+    nonce[12] = security_level;
+    nonce
+}
+
+/// The mask of the 5-octet absolute slot number.
+pub const ASN_MASK: u64 = 0xff_ffff_ffff;
+
+/// Builds the nonce used in TSCH networks from the extended address of the originator
+/// and the absolute slot number, which replaces the frame counter and security level.
+/// Only the lower 40 bits of `asn` are used.
+pub fn asn_nonce(source: u64, asn: u64) -> Nonce {
+    let mut nonce = [0; NONCE_LEN];
+    nonce[..8].copy_from_slice(&source.to_be_bytes());
+    nonce[8..].copy_from_slice(&(asn & ASN_MASK).to_be_bytes()[3..]);
+    nonce
+}
+
+/// CCM* using a single key.
+#[derive(Clone)]
+pub struct Ccm {
+    cipher: Aes128,
+}
+
+impl Ccm {
+    pub fn new(key: &Key) -> Self {
+        Ccm {
+            cipher: Aes128::new(key),
+        }
+    }
+
+    /// Authenticates the open data `a` and the private data `m`, then encrypts `m` in
+    /// place. The MIC is written to `mic`, whose length of 0, 4, 8 or 16 octets selects
+    /// the strength of the authentication.
+    pub fn seal(&self, nonce: &Nonce, a: &[u8], m: &mut [u8], mic: &mut [u8]) {
+        let tag = self.authenticate(nonce, a, m, mic.len());
+        self.transform(nonce, m);
+
+        let s0 = self.keystream_block(nonce, 0);
+        for ((u, t), s) in mic.iter_mut().zip(&tag).zip(&s0) {
+            *u = t ^ s;
+        }
+    }
+
+    /// Decrypts the private data `m` in place and verifies the `mic` over it and the open
+    /// data `a`. If the MIC does not match, `m` is restored and `false` is returned.
+    pub fn open(&self, nonce: &Nonce, a: &[u8], m: &mut [u8], mic: &[u8]) -> bool {
+        self.transform(nonce, m);
+        let tag = self.authenticate(nonce, a, m, mic.len());
+
+        let s0 = self.keystream_block(nonce, 0);
+        let diff = mic
+            .iter()
+            .zip(&tag)
+            .zip(&s0)
+            .fold(0, |diff, ((u, t), s)| diff | (u ^ t ^ s));
+
+        if diff != 0 {
+            self.transform(nonce, m);
+            return false;
+        }
+        true
+    }
+
+    /// Computes the CBC-MAC over the authentication blocks, returning the unencrypted
+    /// authentication tag.
+    fn authenticate(&self, nonce: &Nonce, a: &[u8], m: &[u8], mic_len: usize) -> Block {
+        let mut flags = (LENGTH_LEN - 1) as u8;
+        if !a.is_empty() {
+            flags |= 0x40;
+        }
+        if mic_len > 0 {
+            flags |= (((mic_len - 2) / 2) as u8) << 3;
+        }
+
+        let mut b0 = [0; BLOCK_LEN];
+        b0[0] = flags;
+        b0[1..1 + NONCE_LEN].copy_from_slice(nonce);
+        b0[1 + NONCE_LEN..].copy_from_slice(&(m.len() as u16).to_be_bytes());
+
+        let mut mac = CbcMac::new(&self.cipher, b0);
+        if !a.is_empty() {
+            mac.update(&(a.len() as u16).to_be_bytes());
+            mac.update(a);
+            mac.pad();
+        }
+        mac.update(m);
+        mac.pad();
+
+        mac.state
+    }
+
+    /// Encrypts or decrypts `m` in counter mode, starting at counter 1.
+    fn transform(&self, nonce: &Nonce, m: &mut [u8]) {
+        for (i, chunk) in m.chunks_mut(BLOCK_LEN).enumerate() {
+            let s = self.keystream_block(nonce, i as u16 + 1);
+            for (c, s) in chunk.iter_mut().zip(&s) {
+                *c ^= s;
+            }
+        }
+    }
+
+    fn keystream_block(&self, nonce: &Nonce, counter: u16) -> Block {
+        let mut a = [0; BLOCK_LEN];
+        a[0] = (LENGTH_LEN - 1) as u8;
+        a[1..1 + NONCE_LEN].copy_from_slice(nonce);
+        a[1 + NONCE_LEN..].copy_from_slice(&counter.to_be_bytes());
+
+        self.cipher.encrypt(&mut a);
+        a
+    }
+}
+
+/// The CBC-MAC over a sequence of zero padded blocks.
+struct CbcMac<'a> {
+    cipher: &'a Aes128,
+    state: Block,
+    pos: usize,
+}
+
+impl<'a> CbcMac<'a> {
+    fn new(cipher: &'a Aes128, mut b0: Block) -> Self {
+        cipher.encrypt(&mut b0);
+
+        CbcMac {
+            cipher,
+            state: b0,
+            pos: 0,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            self.state[self.pos] ^= b;
+            self.pos += 1;
+            if self.pos == BLOCK_LEN {
+                self.cipher.encrypt(&mut self.state);
+                self.pos = 0;
+            }
+        }
+    }
+
+    /// Completes the current block, padding it with zeros.
+    fn pad(&mut self) {
+        if self.pos > 0 {
+            self.cipher.encrypt(&mut self.state);
+            self.pos = 0;
+        }
+    }
+}
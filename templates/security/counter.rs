@@ -0,0 +1,222 @@
+//! The frame counters received from the devices of the device table, protecting against
+//! replayed frames, and the frame counter of outgoing frames.
+
+use super::pib::SecurityError;
+
+/// Persists the frame counters of a `FrameCounters` store, so that replay protection
+/// survives a reset.
+pub trait CounterPersistence {
+    /// Loads the last frame counter accepted from the device with `device_handle`, or
+    /// `None` if no frame was accepted yet.
+    fn load(&mut self, device_handle: usize) -> Option<u32>;
+
+    /// Stores `frame_counter` as the last frame counter accepted from the device with
+    /// `device_handle`.
+    fn store(&mut self, device_handle: usize, frame_counter: u32);
+
+    /// Loads the frame counter of the next outgoing frame, `macFrameCounter`.
+    fn load_outgoing(&mut self) -> u32;
+
+    /// Stores the frame counter of the next outgoing frame.
+    fn store_outgoing(&mut self, frame_counter: u32);
+}
+
+/// Keeps the frame counters in memory only.
+impl CounterPersistence for () {
+    fn load(&mut self, _device_handle: usize) -> Option<u32> {
+        None
+    }
+
+    fn store(&mut self, _device_handle: usize, _frame_counter: u32) {}
+
+    fn load_outgoing(&mut self) -> u32 {
+        0
+    }
+
+    fn store_outgoing(&mut self, _frame_counter: u32) {}
+}
+
+/// The last frame counter accepted from each device, indexed by the device handle of
+/// the device table, and the frame counter of the next outgoing frame.
+pub struct FrameCounters<'a, P: CounterPersistence> {
+    counters: &'a mut [Option<u32>],
+    outgoing: u32,
+    persistence: P,
+}
+
+impl<'a, P: CounterPersistence> FrameCounters<'a, P> {
+    /// Creates a store for as many devices as `counters` has entries, loading their
+    /// counters from `persistence`.
+    pub fn new(counters: &'a mut [Option<u32>], mut persistence: P) -> Self {
+        for (device_handle, counter) in counters.iter_mut().enumerate() {
+            *counter = persistence.load(device_handle);
+        }
+        let outgoing = persistence.load_outgoing();
+
+        FrameCounters {
+            counters,
+            outgoing,
+            persistence,
+        }
+    }
+
+    /// The last frame counter accepted from the device with `device_handle`.
+    pub fn get(&self, device_handle: usize) -> Option<u32> {
+        self.counters.get(device_handle).copied().flatten()
+    }
+
+    /// Checks `frame_counter` of a frame received from the device with `device_handle`
+    /// before it is unsecured. Fails if the counter is exhausted, as it cannot be
+    /// followed by another frame, or not greater than the last accepted counter.
+    pub fn check(&self, device_handle: usize, frame_counter: u32) -> Result<(), SecurityError> {
+        if device_handle >= self.counters.len() || frame_counter == u32::max_value() {
+            return Err(SecurityError::CounterError);
+        }
+
+        match self.get(device_handle) {
+            Some(last) if frame_counter <= last => Err(SecurityError::CounterError),
+            _ => Ok(()),
+        }
+    }
+
+    /// Accepts `frame_counter` after the frame received from the device with
+    /// `device_handle` has been unsecured successfully.
+    pub fn update(
+        &mut self,
+        device_handle: usize,
+        frame_counter: u32,
+    ) -> Result<(), SecurityError> {
+        self.check(device_handle, frame_counter)?;
+
+        self.counters[device_handle] = Some(frame_counter);
+        self.persistence.store(device_handle, frame_counter);
+        Ok(())
+    }
+
+    /// The frame counter of the next outgoing frame. Fails if the counter is exhausted.
+    pub fn outgoing(&self) -> Result<u32, SecurityError> {
+        if self.outgoing == u32::max_value() {
+            return Err(SecurityError::CounterError);
+        }
+        Ok(self.outgoing)
+    }
+
+    /// Advances the frame counter after an outgoing frame has been secured.
+    pub fn increment_outgoing(&mut self) -> Result<(), SecurityError> {
+        self.outgoing = self.outgoing()? + 1;
+        self.persistence.store_outgoing(self.outgoing);
+        Ok(())
+    }
+
+    pub fn persistence(&mut self) -> &mut P {
+        &mut self.persistence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Persists the counters of two devices, recording the stores.
+    #[derive(Default)]
+    struct Mock {
+        counters: [Option<u32>; 2],
+        outgoing: u32,
+        stores: usize,
+        outgoing_stores: usize,
+    }
+
+    impl CounterPersistence for Mock {
+        fn load(&mut self, device_handle: usize) -> Option<u32> {
+            self.counters[device_handle]
+        }
+
+        fn store(&mut self, device_handle: usize, frame_counter: u32) {
+            self.counters[device_handle] = Some(frame_counter);
+            self.stores += 1;
+        }
+
+        fn load_outgoing(&mut self) -> u32 {
+            self.outgoing
+        }
+
+        fn store_outgoing(&mut self, frame_counter: u32) {
+            self.outgoing = frame_counter;
+            self.outgoing_stores += 1;
+        }
+    }
+
+    #[test]
+    fn new() {
+        let mock = Mock {
+            counters: [None, Some(7)],
+            outgoing: 3,
+            ..Mock::default()
+        };
+        let mut storage = [Some(1), None];
+        let counters = FrameCounters::new(&mut storage, mock);
+
+        assert_eq!(counters.get(0), None);
+        assert_eq!(counters.get(1), Some(7));
+        assert_eq!(counters.get(2), None);
+        assert_eq!(counters.outgoing(), Ok(3));
+    }
+
+    #[test]
+    fn check() {
+        let mock = Mock {
+            counters: [None, Some(7)],
+            ..Mock::default()
+        };
+        let mut storage = [None; 2];
+        let counters = FrameCounters::new(&mut storage, mock);
+
+        assert_eq!(counters.check(0, 0), Ok(()));
+        assert_eq!(counters.check(1, 8), Ok(()));
+        assert_eq!(counters.check(1, 7), Err(SecurityError::CounterError));
+        assert_eq!(counters.check(1, 6), Err(SecurityError::CounterError));
+        assert_eq!(
+            counters.check(0, u32::max_value()),
+            Err(SecurityError::CounterError)
+        );
+        assert_eq!(counters.check(2, 8), Err(SecurityError::CounterError));
+    }
+
+    #[test]
+    fn update() {
+        let mut storage = [None; 2];
+        let mut counters = FrameCounters::new(&mut storage, Mock::default());
+
+        assert_eq!(counters.update(1, 5), Ok(()));
+        assert_eq!(counters.get(1), Some(5));
+        assert_eq!(counters.persistence().counters, [None, Some(5)]);
+        assert_eq!(counters.persistence().stores, 1);
+
+        assert_eq!(counters.update(1, 5), Err(SecurityError::CounterError));
+        assert_eq!(counters.update(2, 6), Err(SecurityError::CounterError));
+        assert_eq!(counters.get(1), Some(5));
+        assert_eq!(counters.persistence().stores, 1);
+    }
+
+    #[test]
+    fn outgoing() {
+        let mock = Mock {
+            outgoing: u32::max_value() - 1,
+            ..Mock::default()
+        };
+        let mut storage = [];
+        let mut counters = FrameCounters::new(&mut storage, mock);
+
+        assert_eq!(counters.outgoing(), Ok(u32::max_value() - 1));
+        assert_eq!(counters.increment_outgoing(), Ok(()));
+        assert_eq!(counters.persistence().outgoing, u32::max_value());
+        assert_eq!(counters.persistence().outgoing_stores, 1);
+
+        assert_eq!(counters.outgoing(), Err(SecurityError::CounterError));
+        assert_eq!(
+            counters.increment_outgoing(),
+            Err(SecurityError::CounterError)
+        );
+        assert_eq!(counters.persistence().outgoing_stores, 1);
+    }
+}
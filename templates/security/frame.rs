@@ -0,0 +1,280 @@
+//! Securing and unsecuring frames in place using CCM*.
+
+use crate::error::ParseError;
+use crate::header::{frame_type, Header};
+use crate::ie::HeaderIeList;
+
+use super::ccm::{asn_nonce, nonce, Ccm, Nonce, ASN_MASK};
+use super::header::AuxiliarySecurityHeader;
+
+/// The headers of a secured frame and the end of its open payload, which is
+/// authenticated but never encrypted.
+pub(crate) struct Layout {
+    pub(crate) header: Header,
+    pub(crate) aux: AuxiliarySecurityHeader,
+    /// The command frame identifier of MAC command frames, if part of the open payload.
+    pub(crate) command_frame_id: Option<u8>,
+    open_end: usize,
+    /// The length of the frame without the MIC.
+    pub(crate) payload_end: usize,
+}
+
+impl Layout {
+    /// Determines the layout of the frame in `buf`, which excludes the FCS. `with_mic`
+    /// specifies if `buf` ends with a MIC.
+    ///
+    /// The open payload consists of the MHR, the auxiliary security header and the header
+    /// IEs. The command identifier of MAC command frames without payload IEs is also
+    /// part of the open payload. Fails if security is not enabled, the headers are
+    /// malformed or `buf` is too short for the MIC.
+    pub(crate) fn parse(buf: &[u8], with_mic: bool) -> Result<Self, ParseError> {
+        let (header, mut offset) = Header::parse(buf)?;
+        let frame_control = header.frame_control;
+        if !frame_control.security_enabled() {
+            return Err(ParseError::UnexpectedFrame { offset: 0 });
+        }
+
+        let (aux, len) =
+            AuxiliarySecurityHeader::parse(&buf[offset..], frame_control.frame_version())
+                .map_err(|e| e.at(offset))?;
+        offset += len;
+
+        let mic_len = if with_mic {
+            aux.security_control.mic_len()
+        } else {
+            0
+        };
+        let payload_end = buf
+            .len()
+            .checked_sub(mic_len)
+            .filter(|&end| end >= offset)
+            .ok_or(ParseError::Truncated { offset })?;
+
+        let mut payload_ies = false;
+        if frame_control.ie_present() {
+            let ies = HeaderIeList::parse(&buf[offset..payload_end]).map_err(|e| e.at(offset))?;
+            offset += ies.buffer_len();
+            payload_ies = ies.payload_ies().is_some();
+        }
+
+        let mut command_frame_id = None;
+        if frame_control.frame_type() == frame_type::MAC_COMMAND
+            && !payload_ies
+            && offset < payload_end
+        {
+            command_frame_id = Some(buf[offset]);
+            offset += 1;
+        }
+
+        Ok(Layout {
+            header,
+            aux,
+            command_frame_id,
+            open_end: offset,
+            payload_end,
+        })
+    }
+
+    /// The start of the private payload, which is encrypted for security levels
+    /// providing confidentiality. For other levels the whole payload is authenticated
+    /// as open payload.
+    fn private_start(&self) -> usize {
+        if self.aux.security_control.encrypted() {
+            self.open_end
+        } else {
+            self.payload_end
+        }
+    }
+
+    /// Secures the unsecured frame in `buf`, followed by room for the MIC.
+    pub(crate) fn seal(&self, ccm: &Ccm, buf: &mut [u8], nonce: &Nonce) {
+        let private_start = self.private_start();
+        let (a, rest) = buf.split_at_mut(private_start);
+        let (m, mic) = rest.split_at_mut(self.payload_end - private_start);
+
+        ccm.seal(nonce, a, m, mic);
+    }
+
+    /// Unsecures the secured frame in `buf`, returning `false` if the MIC does not match.
+    pub(crate) fn open(&self, ccm: &Ccm, buf: &mut [u8], nonce: &Nonce) -> bool {
+        let private_start = self.private_start();
+        let (a, rest) = buf.split_at_mut(private_start);
+        let (m, mic) = rest.split_at_mut(self.payload_end - private_start);
+
+        ccm.open(nonce, a, m, mic)
+    }
+}
+
+/// Builds the nonce of a frame with the auxiliary security header `aux`, sent by the
+/// originator with the extended address `source`.
+///
+/// If the ASN in nonce field is set, the absolute slot number `asn` of the timeslot the
+/// frame is sent in replaces the frame counter. It must be supplied and, if the frame
+/// counter is not suppressed, match it in its lower 32 bits. Otherwise the frame
+/// counter must be present and `asn` is ignored. Returns `None` if these checks fail.
+pub fn frame_nonce(aux: &AuxiliarySecurityHeader, source: u64, asn: Option<u64>) -> Option<Nonce> {
+    let control = aux.security_control;
+    if !control.asn_in_nonce() {
+        return Some(nonce(source, aux.frame_counter?, control.security_level()));
+    }
+
+    let asn = asn.filter(|&asn| asn <= ASN_MASK)?;
+    match aux.frame_counter {
+        Some(frame_counter) if frame_counter != asn as u32 => None,
+        _ => Some(asn_nonce(source, asn)),
+    }
+}
+
+/// Secures the unsecured frame in the first `len` octets of `buf`, which excludes the
+/// FCS, according to its auxiliary security header. `source` is the extended address
+/// of the originator and `asn` the absolute slot number used by TSCH, see `frame_nonce`.
+/// The payload is encrypted in place and the MIC is appended. Returns the length of the
+/// secured frame, or `None` if the frame does not have security enabled, no nonce can be
+/// built or `buf` is too short for the MIC.
+pub fn secure_frame(
+    ccm: &Ccm,
+    buf: &mut [u8],
+    len: usize,
+    source: u64,
+    asn: Option<u64>,
+) -> Option<usize> {
+    let layout = Layout::parse(buf.get(..len)?, false).ok()?;
+    let control = layout.aux.security_control;
+    let nonce = frame_nonce(&layout.aux, source, asn)?;
+
+    let end = len + control.mic_len();
+    layout.seal(ccm, buf.get_mut(..end)?, &nonce);
+
+    Some(end)
+}
+
+/// Unsecures the secured frame in the first `len` octets of `buf`, which excludes the
+/// FCS. `source` is the extended address of the originator and `asn` the absolute slot
+/// number used by TSCH, see `frame_nonce`. The MIC is verified and the payload is
+/// decrypted in place. Returns the length of the frame without the MIC, or `None` if the
+/// frame is malformed, no nonce can be built or the MIC does not match.
+pub fn unsecure_frame(
+    ccm: &Ccm,
+    buf: &mut [u8],
+    len: usize,
+    source: u64,
+    asn: Option<u64>,
+) -> Option<usize> {
+    let buf = buf.get_mut(..len)?;
+    let layout = Layout::parse(buf, true).ok()?;
+    let nonce = frame_nonce(&layout.aux, source, asn)?;
+
+    if !layout.open(ccm, buf, &nonce) {
+        return None;
+    }
+
+    Some(layout.payload_end)
+}
+
+/// The test vectors of Annex C of IEEE 802.15.4-2006.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::header::{KeyIdentifier, SecurityControl};
+
+    const KEY: [u8; 16] = [
+        0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd, 0xce,
+        0xcf,
+    ];
+    const SOURCE: u64 = 0xacde_4800_0000_0001;
+
+    fn check(unsecured: &[u8], secured: &[u8]) {
+        let ccm = Ccm::new(&KEY);
+        let mut buf = [0; 64];
+
+        buf[..unsecured.len()].copy_from_slice(unsecured);
+        let len = secure_frame(&ccm, &mut buf, unsecured.len(), SOURCE, None).unwrap();
+        assert_eq!(&buf[..len], secured);
+
+        let len = unsecure_frame(&ccm, &mut buf, len, SOURCE, None).unwrap();
+        assert_eq!(&buf[..len], unsecured);
+
+        buf[..secured.len()].copy_from_slice(secured);
+        buf[secured.len() - 1] ^= 1;
+        assert_eq!(
+            unsecure_frame(&ccm, &mut buf, secured.len(), SOURCE, None),
+            None
+        );
+    }
+
+    #[test]
+    fn beacon_frame_mic_64() {
+        let unsecured = [
+            0x08, 0xd0, 0x84, 0x21, 0x43, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x02,
+            0x05, 0x00, 0x00, 0x00, 0x55, 0xcf, 0x00, 0x00, 0x51, 0x52, 0x53, 0x54,
+        ];
+        let secured = [
+            0x08, 0xd0, 0x84, 0x21, 0x43, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x02,
+            0x05, 0x00, 0x00, 0x00, 0x55, 0xcf, 0x00, 0x00, 0x51, 0x52, 0x53, 0x54, 0x22, 0x3b,
+            0xc1, 0xec, 0x84, 0x1a, 0xb5, 0x53,
+        ];
+
+        check(&unsecured, &secured);
+    }
+
+    #[test]
+    fn data_frame_enc() {
+        let unsecured = [
+            0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x04, 0x05, 0x00, 0x00, 0x00, 0x61, 0x62,
+            0x63, 0x64,
+        ];
+        let secured = [
+            0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x04, 0x05, 0x00, 0x00, 0x00, 0xd4, 0x3e,
+            0x02, 0x2b,
+        ];
+
+        let ccm = Ccm::new(&KEY);
+        let mut buf = [0; 64];
+        buf[..unsecured.len()].copy_from_slice(&unsecured);
+        let len = secure_frame(&ccm, &mut buf, unsecured.len(), SOURCE, None).unwrap();
+        assert_eq!(&buf[..len], &secured[..]);
+
+        let len = unsecure_frame(&ccm, &mut buf, len, SOURCE, None).unwrap();
+        assert_eq!(&buf[..len], &unsecured[..]);
+    }
+
+    #[test]
+    fn mac_command_frame_enc_mic_64() {
+        let unsecured = [
+            0x2b, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0xff,
+            0xff, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x06, 0x05, 0x00, 0x00, 0x00,
+            0x01, 0xce,
+        ];
+        let secured = [
+            0x2b, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0xff,
+            0xff, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x06, 0x05, 0x00, 0x00, 0x00,
+            0x01, 0xd8, 0x4f, 0xde, 0x52, 0x90, 0x61, 0xf9, 0xc6, 0xf1,
+        ];
+
+        check(&unsecured, &secured);
+    }
+
+    #[test]
+    fn asn_nonce() {
+        let mut aux = AuxiliarySecurityHeader {
+            security_control: SecurityControl(0b0110_1101),
+            frame_counter: None,
+            key_identifier: KeyIdentifier::Only { index: 1 },
+        };
+        let asn = 0x12_3456_789a;
+
+        assert_eq!(
+            frame_nonce(&aux, SOURCE, Some(asn)),
+            Some([0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01, 0x12, 0x34, 0x56, 0x78, 0x9a])
+        );
+        assert_eq!(frame_nonce(&aux, SOURCE, None), None);
+        assert_eq!(frame_nonce(&aux, SOURCE, Some(1 << 40)), None);
+
+        aux.frame_counter = Some(0x3456_789a);
+        assert!(frame_nonce(&aux, SOURCE, Some(asn)).is_some());
+        aux.frame_counter = Some(0x3456_789b);
+        assert_eq!(frame_nonce(&aux, SOURCE, Some(asn)), None);
+    }
+}
@@ -0,0 +1,342 @@
+//! Decoding and encoding of the auxiliary security header, selecting its alternatives
+//! from the security control field and the frame version.
+
+use core::convert::TryInto;
+
+use crate::error::{get, ParseError};
+use crate::header::frame_version;
+
+/// The security levels of the security control field.
+pub mod security_levels {
+    pub const NONE: u8 = 0b000;
+    pub const MIC_32: u8 = 0b001;
+    pub const MIC_64: u8 = 0b010;
+    pub const MIC_128: u8 = 0b011;
+    pub const ENC_MIC_32: u8 = 0b101;
+    pub const ENC_MIC_64: u8 = 0b110;
+    pub const ENC_MIC_128: u8 = 0b111;
+}
+
+/// The key identifier modes of the security control field.
+pub mod key_id_modes {
+    pub const IMPLICIT: u8 = 0b00;
+    pub const KEY_INDEX: u8 = 0b01;
+    pub const KEY_SOURCE_4: u8 = 0b10;
+    pub const KEY_SOURCE_8: u8 = 0b11;
+}
+
+pub use crate::layout::SecurityControl;
+
+impl SecurityControl {
+    /// The length of the MIC appended to the payload.
+    pub fn mic_len(&self) -> usize {
+        match self.security_level() & 0b11 {
+            0b00 => 0,
+            0b01 => 4,
+            0b10 => 8,
+            _ => 16,
+        }
+    }
+
+    /// Determines if the payload is encrypted.
+    pub fn encrypted(&self) -> bool {
+        self.security_level() & 0b100 != 0
+    }
+
+    /// The security control field as understood by frames of `version`. The frame
+    /// counter suppression and ASN in nonce fields are reserved in frame version 2006
+    /// and ignored. Returns `None` if `version` does not use the auxiliary security
+    /// header.
+    pub fn for_version(&self, version: u8) -> Option<Self> {
+        match version {
+            frame_version::VERSION_2006 => {
+                let mut security_control = *self;
+                security_control.set_frame_counter_suppressed(false);
+                security_control.set_asn_in_nonce(false);
+                Some(security_control)
+            }
+            frame_version::CURRENT => Some(*self),
+            _ => None,
+        }
+    }
+}
+
+/// Determines if frames of `version` are secured using the auxiliary security header.
+/// Frame version 2003 used a different security scheme, which is not supported.
+pub fn security_supported(version: u8) -> bool {
+    matches!(
+        version,
+        frame_version::VERSION_2006 | frame_version::CURRENT
+    )
+}
+
+/// Determines if `level` is reserved in frames of `version`. Encryption without
+/// authentication is reserved in the current frame version.
+pub fn security_level_reserved(version: u8, level: u8) -> bool {
+    version == frame_version::CURRENT && level == 0b100
+}
+
+/// The key identifier of the auxiliary security header, corresponding to the
+/// `key_identifier` alternatives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyIdentifier {
+    None,
+    Only { index: u8 },
+    Short { source: u32, index: u8 },
+    Long { source: u64, index: u8 },
+}
+
+impl KeyIdentifier {
+    /// The key identifier mode of the security control field which selects this key
+    /// identifier.
+    pub fn mode(&self) -> u8 {
+        match self {
+            KeyIdentifier::None => key_id_modes::IMPLICIT,
+            KeyIdentifier::Only { .. } => key_id_modes::KEY_INDEX,
+            KeyIdentifier::Short { .. } => key_id_modes::KEY_SOURCE_4,
+            KeyIdentifier::Long { .. } => key_id_modes::KEY_SOURCE_8,
+        }
+    }
+
+    pub fn buffer_len(&self) -> usize {
+        let mut security_control = SecurityControl::default();
+        security_control.set_key_id_mode(self.mode());
+        security_control.key_identifier_len()
+    }
+
+    /// Decodes the key identifier selected by `security_control` at the start of `buf`.
+    fn parse(security_control: &SecurityControl, buf: &[u8]) -> Result<Self, ParseError> {
+        let buf = get(buf, 0, security_control.key_identifier_len())?;
+
+        Ok(match security_control.key_id_mode() {
+            key_id_modes::IMPLICIT => KeyIdentifier::None,
+            key_id_modes::KEY_INDEX => KeyIdentifier::Only { index: buf[0] },
+            key_id_modes::KEY_SOURCE_4 => KeyIdentifier::Short {
+                source: u32::from_le_bytes(buf[..4].try_into().unwrap()),
+                index: buf[4],
+            },
+            _ => KeyIdentifier::Long {
+                source: u64::from_le_bytes(buf[..8].try_into().unwrap()),
+                index: buf[8],
+            },
+        })
+    }
+
+    fn emit(&self, buf: &mut [u8]) {
+        match *self {
+            KeyIdentifier::None => (),
+            KeyIdentifier::Only { index } => buf[0] = index,
+            KeyIdentifier::Short { source, index } => {
+                buf[..4].copy_from_slice(&source.to_le_bytes());
+                buf[4] = index;
+            }
+            KeyIdentifier::Long { source, index } => {
+                buf[..8].copy_from_slice(&source.to_le_bytes());
+                buf[8] = index;
+            }
+        }
+    }
+}
+
+/// A decoded auxiliary security header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuxiliarySecurityHeader {
+    pub security_control: SecurityControl,
+    /// Absent if the frame counter is suppressed.
+    pub frame_counter: Option<u32>,
+    pub key_identifier: KeyIdentifier,
+}
+
+impl AuxiliarySecurityHeader {
+    /// Decodes the auxiliary security header of a frame of `version` at the start of
+    /// `buf`, returning the header and its length. Fails if `buf` is truncated, the frame
+    /// version does not support security or the security level is reserved, such as
+    /// encryption without authentication in the current frame version.
+    pub fn parse(buf: &[u8], version: u8) -> Result<(Self, usize), ParseError> {
+        let security_control = SecurityControl(get(buf, 0, 1)?[0])
+            .for_version(version)
+            .ok_or(if version == frame_version::VERSION_2003 {
+                ParseError::UnsupportedSecurity { offset: 0 }
+            } else {
+                ParseError::ReservedFrameVersion { offset: 0 }
+            })?;
+        if security_level_reserved(version, security_control.security_level()) {
+            return Err(ParseError::ReservedSecurityLevel { offset: 0 });
+        }
+        let mut offset = 1;
+
+        let frame_counter = if security_control.frame_counter_present() {
+            let frame_counter =
+                u32::from_le_bytes(get(buf, offset, offset + 4)?.try_into().unwrap());
+            offset += 4;
+            Some(frame_counter)
+        } else {
+            None
+        };
+
+        let key_identifier =
+            KeyIdentifier::parse(&security_control, &buf[offset..]).map_err(|e| e.at(offset))?;
+        offset += key_identifier.buffer_len();
+
+        let header = AuxiliarySecurityHeader {
+            security_control,
+            frame_counter,
+            key_identifier,
+        };
+
+        Ok((header, offset))
+    }
+
+    /// The encoded length of the header.
+    pub fn buffer_len(&self) -> usize {
+        let frame_counter_len = if self.frame_counter.is_some() { 4 } else { 0 };
+
+        1 + frame_counter_len + self.key_identifier.buffer_len()
+    }
+
+    /// Encodes the header of a frame of `version` into the start of `buf`. The frame
+    /// counter suppression and key identifier mode of the security control field are
+    /// derived from the fields. Returns the length of the header, or `None` if `buf` is
+    /// too short or the frame version cannot express the header, as frame version 2006
+    /// requires the frame counter and does not support the ASN in nonce.
+    pub fn emit(&self, buf: &mut [u8], version: u8) -> Option<usize> {
+        let len = self.buffer_len();
+        let buf = buf.get_mut(..len)?;
+
+        let mut security_control = self.security_control.for_version(version)?;
+        if version == frame_version::VERSION_2006
+            && (self.frame_counter.is_none() || self.security_control.asn_in_nonce())
+        {
+            return None;
+        }
+        security_control.set_frame_counter_suppressed(self.frame_counter.is_none());
+        security_control.set_key_id_mode(self.key_identifier.mode());
+        buf[0] = security_control.0;
+
+        let mut offset = 1;
+        if let Some(frame_counter) = self.frame_counter {
+            buf[offset..offset + 4].copy_from_slice(&frame_counter.to_le_bytes());
+            offset += 4;
+        }
+        self.key_identifier.emit(&mut buf[offset..]);
+
+        Some(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_versions() {
+        // ENC-MIC-32 with a key index, the frame counter suppression and ASN in nonce
+        // fields set
+        let buf = [0x6d, 0x05, 0x00, 0x00, 0x00, 0x01];
+
+        let (aux, len) = AuxiliarySecurityHeader::parse(&buf, frame_version::VERSION_2006).unwrap();
+        assert_eq!(aux.security_control, SecurityControl(0x0d));
+        assert_eq!(aux.frame_counter, Some(5));
+        assert_eq!(aux.key_identifier, KeyIdentifier::Only { index: 1 });
+        assert_eq!(len, 6);
+
+        let (aux, len) = AuxiliarySecurityHeader::parse(&buf, frame_version::CURRENT).unwrap();
+        assert_eq!(aux.frame_counter, None);
+        assert_eq!(aux.key_identifier, KeyIdentifier::Only { index: 0x05 });
+        assert_eq!(len, 2);
+
+        assert_eq!(
+            AuxiliarySecurityHeader::parse(&buf, frame_version::VERSION_2003),
+            Err(ParseError::UnsupportedSecurity { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn key_identifiers() {
+        // MIC-32 with the frame counter suppressed, the key identifier mode is set for
+        // each of the expected key identifiers
+        let buf = [0x21, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09];
+        let expected = [
+            (KeyIdentifier::None, 1),
+            (KeyIdentifier::Only { index: 0x01 }, 2),
+            (
+                KeyIdentifier::Short {
+                    source: 0x0403_0201,
+                    index: 0x05,
+                },
+                6,
+            ),
+            (
+                KeyIdentifier::Long {
+                    source: 0x0807_0605_0403_0201,
+                    index: 0x09,
+                },
+                10,
+            ),
+        ];
+
+        for (mode, &(key_identifier, len)) in expected.iter().enumerate() {
+            let mut buf = buf;
+            buf[0] |= (mode as u8) << 3;
+
+            let (aux, parsed_len) =
+                AuxiliarySecurityHeader::parse(&buf, frame_version::CURRENT).unwrap();
+            assert_eq!(aux.frame_counter, None);
+            assert_eq!(aux.key_identifier, key_identifier);
+            assert_eq!(parsed_len, len);
+            assert_eq!(aux.buffer_len(), len);
+
+            if len > 1 {
+                assert_eq!(
+                    AuxiliarySecurityHeader::parse(&buf[..len - 1], frame_version::CURRENT),
+                    Err(ParseError::Truncated { offset: 1 })
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reserved_security_level() {
+        // ENC without a MIC, reserved in the current frame version only
+        let buf = [0x04, 0x05, 0x00, 0x00, 0x00];
+
+        assert_eq!(
+            AuxiliarySecurityHeader::parse(&buf, frame_version::CURRENT),
+            Err(ParseError::ReservedSecurityLevel { offset: 0 })
+        );
+        let (aux, _) = AuxiliarySecurityHeader::parse(&buf, frame_version::VERSION_2006).unwrap();
+        assert_eq!(aux.security_control.security_level(), 0b100);
+    }
+
+    #[test]
+    fn emit() {
+        let mut security_control = SecurityControl(0);
+        security_control.set_security_level(security_levels::ENC_MIC_32);
+        let aux = AuxiliarySecurityHeader {
+            security_control,
+            frame_counter: Some(5),
+            key_identifier: KeyIdentifier::Only { index: 1 },
+        };
+
+        let mut buf = [0; 6];
+        assert_eq!(aux.emit(&mut buf, frame_version::VERSION_2006), Some(6));
+        assert_eq!(buf, [0x0d, 0x05, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(aux.emit(&mut buf[..5], frame_version::VERSION_2006), None);
+
+        // frame version 2006 always carries the frame counter and cannot use the ASN
+        let suppressed = AuxiliarySecurityHeader {
+            frame_counter: None,
+            ..aux
+        };
+        assert_eq!(suppressed.emit(&mut buf, frame_version::VERSION_2006), None);
+        assert_eq!(suppressed.emit(&mut buf, frame_version::CURRENT), Some(2));
+        assert_eq!(buf[..2], [0x2d, 0x01]);
+
+        security_control.set_asn_in_nonce(true);
+        let asn = AuxiliarySecurityHeader {
+            security_control,
+            ..aux
+        };
+        assert_eq!(asn.emit(&mut buf, frame_version::VERSION_2006), None);
+    }
+}
@@ -0,0 +1,120 @@
+//! The incoming frame security procedure, unsecuring received frames using the security
+//! PIB and the frame counters of the originators.
+
+use crate::header::Header;
+
+use super::ccm::Ccm;
+use super::counter::{CounterPersistence, FrameCounters};
+use super::frame::{frame_nonce, Layout};
+use super::header::security_supported;
+use super::pib::{IncomingSecurity, SecurityError, SecurityPib};
+
+/// Unsecures the secured frame received in the first `len` octets of `buf`, which
+/// excludes the FCS. `asn` is the absolute slot number used by TSCH, see `frame_nonce`.
+///
+/// The key and originator are resolved from `pib` and the frame counter is checked
+/// against the last counter accepted from the originator. Frames with a suppressed
+/// frame counter rely on the ASN for replay protection. The MIC is verified and the
+/// payload is decrypted in place, then the frame counter is accepted. Returns the length
+/// of the frame without the MIC and the resolved security. Frames of version 2003 are
+/// reported as `UnsupportedLegacy`.
+pub fn unsecure_incoming<'a, P: CounterPersistence>(
+    pib: &SecurityPib<'a>,
+    counters: &mut FrameCounters<P>,
+    buf: &mut [u8],
+    len: usize,
+    asn: Option<u64>,
+) -> Result<(usize, IncomingSecurity<'a>), SecurityError> {
+    let buf = buf.get_mut(..len).ok_or(SecurityError::InvalidFrame)?;
+    let (header, _) = Header::parse(buf)?;
+    if !security_supported(header.frame_control.frame_version()) {
+        return Err(SecurityError::UnsupportedLegacy);
+    }
+
+    let layout = Layout::parse(buf, true)?;
+
+    let security = pib.resolve_incoming(&layout.header, &layout.aux, layout.command_frame_id)?;
+    let frame_counter = layout.aux.frame_counter;
+    if let Some(frame_counter) = frame_counter {
+        counters.check(security.device_handle, frame_counter)?;
+    }
+
+    let source = security.device_descriptor.ext_address;
+    let nonce = frame_nonce(&layout.aux, source, asn).ok_or(SecurityError::InvalidFrame)?;
+    let ccm = Ccm::new(&security.key_descriptor.key);
+    if !layout.open(&ccm, buf, &nonce) {
+        return Err(SecurityError::AuthenticationFailed);
+    }
+
+    if let Some(frame_counter) = frame_counter {
+        counters.update(security.device_handle, frame_counter)?;
+    }
+
+    Ok((layout.payload_end, security))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::frame_type;
+    use crate::security::pib::{
+        DeviceDescriptor, KeyDescriptor, KeyDeviceDescriptor, KeyIdLookup, KeyUsageDescriptor,
+    };
+
+    #[test]
+    fn replayed_frame() {
+        let devices = [DeviceDescriptor {
+            pan_id: 0x4321,
+            short_address: 0xfffe,
+            ext_address: 0xacde_4800_0000_0001,
+            exempt: false,
+        }];
+        let keys = [KeyDescriptor {
+            key_id_lookup_list: &[KeyIdLookup::from_extended_address(0xacde_4800_0000_0001)],
+            key_device_list: &[KeyDeviceDescriptor {
+                device_handle: 0,
+                unique_device: true,
+                blacklisted: false,
+            }],
+            key_usage_list: &[KeyUsageDescriptor {
+                frame_type: frame_type::DATA,
+                command_frame_id: 0,
+            }],
+            key: [
+                0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd,
+                0xce, 0xcf,
+            ],
+        }];
+        let pib = SecurityPib {
+            key_table: &keys,
+            device_table: &devices,
+            security_level_table: &[],
+            default_key_source: 0,
+            pan_id: 0x4321,
+            pan_coord_short_address: 0x0000,
+            pan_coord_extended_address: 0xacde_4800_0000_0000,
+            extended_address: 0xacde_4800_0000_0000,
+        };
+
+        // the secured data frame of Annex C of IEEE 802.15.4-2006
+        let secured = [
+            0x69, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x04, 0x05, 0x00, 0x00, 0x00, 0xd4, 0x3e,
+            0x02, 0x2b,
+        ];
+        let mut storage = [None];
+        let mut counters = FrameCounters::new(&mut storage, ());
+
+        let mut buf = secured;
+        let (len, security) = unsecure_incoming(&pib, &mut counters, &mut buf, 30, None).unwrap();
+        assert_eq!(&buf[len - 4..len], b"abcd");
+        assert_eq!(security.device_handle, 0);
+        assert_eq!(counters.get(0), Some(5));
+
+        let mut buf = secured;
+        assert_eq!(
+            unsecure_incoming(&pib, &mut counters, &mut buf, 30, None).map(|(len, _)| len),
+            Err(SecurityError::CounterError)
+        );
+    }
+}
@@ -0,0 +1,654 @@
+//! The security related attributes of the MAC PIB, the key, device and security level
+//! tables, and the procedures resolving the key and originator of a received frame.
+
+use crate::error::ParseError;
+use crate::header::{frame_type, Address, Header};
+
+use super::ccm::Key;
+use super::header::{AuxiliarySecurityHeader, KeyIdentifier};
+
+/// The short address of the PAN coordinator indicating that it uses its extended
+/// address.
+pub const SHORT_ADDRESS_UNAVAILABLE: u16 = 0xfffe;
+
+/// The failures of the incoming and outgoing frame security procedures, named after
+/// the status codes of the standard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecurityError {
+    /// The frame has security enabled but uses security level 0, or requests security
+    /// features its frame version cannot express.
+    UnsupportedSecurity,
+    /// The frame uses the security of frame version 2003, which is not supported.
+    UnsupportedLegacy,
+    /// No key descriptor matches the key identifier, or no device descriptor which
+    /// is not blacklisted for the key matches the originator of a received frame.
+    UnavailableKey,
+    /// The security level is below the minimum required for the frame type.
+    ImproperSecurityLevel,
+    /// The key may not be used for the frame type.
+    ImproperKeyType,
+    /// The frame counter was already used by the originator or is exhausted.
+    CounterError,
+    /// The frame does not fit in the buffer, has the wrong security enabled field or its
+    /// nonce cannot be built.
+    InvalidFrame,
+    /// The headers of the frame could not be decoded.
+    MalformedFrame(ParseError),
+    /// The MIC does not match, `SECURITY_ERROR` in the standard.
+    AuthenticationFailed,
+}
+
+impl From<ParseError> for SecurityError {
+    fn from(e: ParseError) -> Self {
+        SecurityError::MalformedFrame(e)
+    }
+}
+
+/// The lookup data identifying a key, either 5 or 9 octets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyIdLookup {
+    Short([u8; 5]),
+    Long([u8; 9]),
+}
+
+impl KeyIdLookup {
+    /// The lookup data of an implicit key shared with a device using a short address.
+    pub fn from_short_address(pan_id: u16, address: u16) -> Self {
+        let mut data = [0; 5];
+        data[..2].copy_from_slice(&pan_id.to_le_bytes());
+        data[2..4].copy_from_slice(&address.to_le_bytes());
+        KeyIdLookup::Short(data)
+    }
+
+    /// The lookup data of an implicit key shared with a device using an extended
+    /// address.
+    pub fn from_extended_address(address: u64) -> Self {
+        let mut data = [0; 9];
+        data[..8].copy_from_slice(&address.to_le_bytes());
+        KeyIdLookup::Long(data)
+    }
+
+    /// The lookup data of a key identified by a 4-octet key source and key index.
+    pub fn from_key_source_4(source: u32, index: u8) -> Self {
+        let mut data = [0; 5];
+        data[..4].copy_from_slice(&source.to_le_bytes());
+        data[4] = index;
+        KeyIdLookup::Short(data)
+    }
+
+    /// The lookup data of a key identified by an 8-octet key source and key index,
+    /// also used with `macDefaultKeySource` for keys identified by the key index only.
+    pub fn from_key_source_8(source: u64, index: u8) -> Self {
+        let mut data = [0; 9];
+        data[..8].copy_from_slice(&source.to_le_bytes());
+        data[8] = index;
+        KeyIdLookup::Long(data)
+    }
+}
+
+/// A device using a key, referring to an entry of the device table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyDeviceDescriptor {
+    /// The index of the device descriptor in the device table.
+    pub device_handle: usize,
+    /// The key is only used by this device, which is then identified by the key alone.
+    pub unique_device: bool,
+    /// The key may no longer be used by this device.
+    pub blacklisted: bool,
+}
+
+/// A frame type, and for MAC command frames the command frame identifier, a key may be
+/// used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyUsageDescriptor {
+    pub frame_type: u8,
+    /// Only compared for MAC command frames.
+    pub command_frame_id: u8,
+}
+
+/// An entry of the key table, `macKeyTable`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyDescriptor<'a> {
+    pub key_id_lookup_list: &'a [KeyIdLookup],
+    pub key_device_list: &'a [KeyDeviceDescriptor],
+    pub key_usage_list: &'a [KeyUsageDescriptor],
+    pub key: Key,
+}
+
+/// An entry of the device table, `macDeviceTable`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceDescriptor {
+    pub pan_id: u16,
+    pub short_address: u16,
+    pub ext_address: u64,
+    /// The device may send frames using security level 0 where the security level
+    /// table allows it.
+    pub exempt: bool,
+}
+
+/// An entry of the security level table, `macSecurityLevelTable`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SecurityLevelDescriptor {
+    pub frame_type: u8,
+    /// Only compared for MAC command frames.
+    pub command_frame_id: u8,
+    pub security_minimum: u8,
+    /// Frames of exempt devices may use security level 0.
+    pub device_override_security_minimum: bool,
+}
+
+/// Determines if `level` provides at least the protection of `minimum`, that is if
+/// neither its MIC length nor its confidentiality are lower.
+pub fn security_level_satisfies(level: u8, minimum: u8) -> bool {
+    (level & 0b11) >= (minimum & 0b11) && (level & 0b100) >= (minimum & 0b100)
+}
+
+/// The originator of a received frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceAddress {
+    pub pan_id: u16,
+    pub address: Address,
+}
+
+/// The key and originator resolved for a received frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IncomingSecurity<'a> {
+    pub key_descriptor: &'a KeyDescriptor<'a>,
+    pub device_handle: usize,
+    pub device_descriptor: &'a DeviceDescriptor,
+}
+
+/// The security related attributes of the MAC PIB.
+#[derive(Clone, Copy, Debug)]
+pub struct SecurityPib<'a> {
+    pub key_table: &'a [KeyDescriptor<'a>],
+    pub device_table: &'a [DeviceDescriptor],
+    pub security_level_table: &'a [SecurityLevelDescriptor],
+    pub default_key_source: u64,
+    pub pan_id: u16,
+    pub pan_coord_short_address: u16,
+    pub pan_coord_extended_address: u64,
+    /// The extended address of this device, `macExtendedAddress`, used in the nonce of
+    /// outgoing frames.
+    pub extended_address: u64,
+}
+
+impl<'a> SecurityPib<'a> {
+    /// The originator of a frame with `header`. Frames without a source address are
+    /// sent by the PAN coordinator.
+    pub fn device_address(&self, header: &Header) -> DeviceAddress {
+        let pan_id = header.src_pan.or(header.dst_pan);
+        self.resolve_device(pan_id, header.src_address)
+    }
+
+    /// The recipient of a frame with `header`. Frames without a destination address
+    /// are sent to the PAN coordinator.
+    pub fn recipient_address(&self, header: &Header) -> DeviceAddress {
+        self.resolve_device(header.dst_pan, header.dst_address)
+    }
+
+    fn resolve_device(&self, pan_id: Option<u16>, address: Address) -> DeviceAddress {
+        let pan_id = pan_id.unwrap_or(self.pan_id);
+
+        match address {
+            Address::None if self.pan_coord_short_address == SHORT_ADDRESS_UNAVAILABLE => {
+                DeviceAddress {
+                    pan_id: self.pan_id,
+                    address: Address::Extended(self.pan_coord_extended_address),
+                }
+            }
+            Address::None => DeviceAddress {
+                pan_id: self.pan_id,
+                address: Address::Short(self.pan_coord_short_address),
+            },
+            address => DeviceAddress { pan_id, address },
+        }
+    }
+
+    /// The lookup data of the key used by `device` with `key_identifier`. Returns `None`
+    /// for implicit keys if `device` has no address.
+    pub fn key_id_lookup(
+        &self,
+        key_identifier: &KeyIdentifier,
+        device: &DeviceAddress,
+    ) -> Option<KeyIdLookup> {
+        Some(match *key_identifier {
+            KeyIdentifier::None => match device.address {
+                Address::Extended(address) => KeyIdLookup::from_extended_address(address),
+                Address::Short(address) => KeyIdLookup::from_short_address(device.pan_id, address),
+                Address::None => return None,
+            },
+            KeyIdentifier::Only { index } => {
+                KeyIdLookup::from_key_source_8(self.default_key_source, index)
+            }
+            KeyIdentifier::Short { source, index } => KeyIdLookup::from_key_source_4(source, index),
+            KeyIdentifier::Long { source, index } => KeyIdLookup::from_key_source_8(source, index),
+        })
+    }
+
+    /// The KeyDescriptor lookup procedure, finding the key descriptor matching `lookup`.
+    pub fn lookup_key(&self, lookup: &KeyIdLookup) -> Option<&'a KeyDescriptor<'a>> {
+        self.key_table
+            .iter()
+            .find(|key| key.key_id_lookup_list.contains(lookup))
+    }
+
+    /// The outgoing frame key retrieval procedure, finding the key used to secure a frame
+    /// with `header` and `key_identifier`. Implicit keys are shared with the recipient.
+    pub fn outgoing_key(
+        &self,
+        header: &Header,
+        key_identifier: &KeyIdentifier,
+    ) -> Result<&'a KeyDescriptor<'a>, SecurityError> {
+        let device = self.recipient_address(header);
+        self.key_id_lookup(key_identifier, &device)
+            .and_then(|lookup| self.lookup_key(&lookup))
+            .ok_or(SecurityError::UnavailableKey)
+    }
+
+    /// The DeviceDescriptor lookup procedure, determining if `descriptor` describes
+    /// `device`.
+    pub fn device_matches(descriptor: &DeviceDescriptor, device: &DeviceAddress) -> bool {
+        match device.address {
+            Address::Short(address) => {
+                descriptor.pan_id == device.pan_id && descriptor.short_address == address
+            }
+            Address::Extended(address) => descriptor.ext_address == address,
+            Address::None => false,
+        }
+    }
+
+    /// The blacklist checking procedure, finding the device descriptor of `device` among
+    /// the devices using `key`, which are not blacklisted. A device the key is unique to
+    /// is found without comparing its address. Returns the device descriptor and its
+    /// index in the device table.
+    pub fn lookup_device(
+        &self,
+        key: &KeyDescriptor,
+        device: &DeviceAddress,
+    ) -> Option<(usize, &'a DeviceDescriptor)> {
+        key.key_device_list
+            .iter()
+            .filter(|key_device| !key_device.blacklisted)
+            .filter_map(|key_device| {
+                let handle = key_device.device_handle;
+                let descriptor = self.device_table.get(handle)?;
+                if key_device.unique_device || Self::device_matches(descriptor, device) {
+                    Some((handle, descriptor))
+                } else {
+                    None
+                }
+            })
+            .next()
+    }
+
+    /// The incoming security level checking procedure. Returns `Some(true)` if the
+    /// security level passes, `Some(false)` if it only passes for exempt devices and
+    /// `None` if it fails.
+    pub fn check_security_level(
+        &self,
+        security_level: u8,
+        frame_type: u8,
+        command_frame_id: Option<u8>,
+    ) -> Option<bool> {
+        let descriptor = match self.security_level_table.iter().find(|d| {
+            Self::applies(
+                d.frame_type,
+                d.command_frame_id,
+                frame_type,
+                command_frame_id,
+            )
+        }) {
+            Some(descriptor) => descriptor,
+            None => return Some(true),
+        };
+
+        if security_level_satisfies(security_level, descriptor.security_minimum) {
+            Some(true)
+        } else if descriptor.device_override_security_minimum && security_level == 0 {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if a frame with `header` received without security may be accepted, which
+    /// requires the security level table to permit security level 0 for the frame, or
+    /// to permit it for exempt devices and the originator to be an exempt device of the
+    /// device table. `command_frame_id` is the command frame identifier of MAC command
+    /// frames.
+    pub fn check_unsecured(
+        &self,
+        header: &Header,
+        command_frame_id: Option<u8>,
+    ) -> Result<(), SecurityError> {
+        let frame_type = header.frame_control.frame_type();
+        match self.check_security_level(0, frame_type, command_frame_id) {
+            Some(true) => Ok(()),
+            Some(false) => {
+                let device = self.device_address(header);
+                if self
+                    .device_table
+                    .iter()
+                    .any(|d| d.exempt && Self::device_matches(d, &device))
+                {
+                    Ok(())
+                } else {
+                    Err(SecurityError::ImproperSecurityLevel)
+                }
+            }
+            None => Err(SecurityError::ImproperSecurityLevel),
+        }
+    }
+
+    /// The key usage policy checking procedure.
+    pub fn check_key_usage(
+        key: &KeyDescriptor,
+        frame_type: u8,
+        command_frame_id: Option<u8>,
+    ) -> bool {
+        key.key_usage_list.iter().any(|u| {
+            Self::applies(
+                u.frame_type,
+                u.command_frame_id,
+                frame_type,
+                command_frame_id,
+            )
+        })
+    }
+
+    fn applies(
+        descriptor_frame_type: u8,
+        descriptor_command_frame_id: u8,
+        frame_type: u8,
+        command_frame_id: Option<u8>,
+    ) -> bool {
+        descriptor_frame_type == frame_type
+            && (frame_type != frame_type::MAC_COMMAND
+                || command_frame_id == Some(descriptor_command_frame_id))
+    }
+
+    /// Resolves the key and the originator of a received frame with `header` and the
+    /// auxiliary security header `aux`, performing the key lookup, blacklist checking,
+    /// security level checking and key usage policy checking steps of the incoming
+    /// frame security procedure. `command_frame_id` is the command frame identifier of
+    /// MAC command frames.
+    pub fn resolve_incoming(
+        &self,
+        header: &Header,
+        aux: &AuxiliarySecurityHeader,
+        command_frame_id: Option<u8>,
+    ) -> Result<IncomingSecurity<'a>, SecurityError> {
+        let security_level = aux.security_control.security_level();
+        if security_level == 0 {
+            return Err(SecurityError::UnsupportedSecurity);
+        }
+
+        let device = self.device_address(header);
+        let lookup = self.key_id_lookup(&aux.key_identifier, &device);
+        let key_descriptor = lookup
+            .and_then(|lookup| self.lookup_key(&lookup))
+            .ok_or(SecurityError::UnavailableKey)?;
+        let (device_handle, device_descriptor) = self
+            .lookup_device(key_descriptor, &device)
+            .ok_or(SecurityError::UnavailableKey)?;
+
+        let frame_type = header.frame_control.frame_type();
+        // only unsecured frames may pass conditionally, see `check_unsecured`
+        if self.check_security_level(security_level, frame_type, command_frame_id) != Some(true) {
+            return Err(SecurityError::ImproperSecurityLevel);
+        }
+
+        if !Self::check_key_usage(key_descriptor, frame_type, command_frame_id) {
+            return Err(SecurityError::ImproperKeyType);
+        }
+
+        Ok(IncomingSecurity {
+            key_descriptor,
+            device_handle,
+            device_descriptor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::FrameControl;
+    use crate::security::header::{security_levels, SecurityControl};
+
+    const DEVICES: [DeviceDescriptor; 2] = [
+        DeviceDescriptor {
+            pan_id: 0x4321,
+            short_address: 0x0001,
+            ext_address: 0xacde_4800_0000_0001,
+            exempt: false,
+        },
+        DeviceDescriptor {
+            pan_id: 0x4321,
+            short_address: 0x0002,
+            ext_address: 0xacde_4800_0000_0002,
+            exempt: false,
+        },
+    ];
+    const DATA: KeyUsageDescriptor = KeyUsageDescriptor {
+        frame_type: frame_type::DATA,
+        command_frame_id: 0,
+    };
+
+    fn header(src_address: Address) -> Header {
+        let mut frame_control = FrameControl(0);
+        frame_control.set_frame_type(frame_type::DATA);
+        Header {
+            frame_control,
+            sequence_number: Some(1),
+            dst_pan: Some(0x4321),
+            dst_address: Address::Short(0x0000),
+            src_pan: None,
+            src_address,
+        }
+    }
+
+    fn aux(security_level: u8, key_identifier: KeyIdentifier) -> AuxiliarySecurityHeader {
+        let mut security_control = SecurityControl(0);
+        security_control.set_security_level(security_level);
+        AuxiliarySecurityHeader {
+            security_control,
+            frame_counter: Some(1),
+            key_identifier,
+        }
+    }
+
+    #[test]
+    fn resolve_incoming() {
+        let implicit = [KeyIdLookup::from_extended_address(0xacde_4800_0000_0002)];
+        let indexed = [KeyIdLookup::from_key_source_8(0xff, 3)];
+        let key_devices = [
+            KeyDeviceDescriptor {
+                device_handle: 0,
+                unique_device: false,
+                blacklisted: true,
+            },
+            KeyDeviceDescriptor {
+                device_handle: 1,
+                unique_device: false,
+                blacklisted: false,
+            },
+        ];
+        let keys = [
+            KeyDescriptor {
+                key_id_lookup_list: &implicit,
+                key_device_list: &key_devices[1..],
+                key_usage_list: &[DATA],
+                key: [1; 16],
+            },
+            KeyDescriptor {
+                key_id_lookup_list: &indexed,
+                key_device_list: &key_devices,
+                key_usage_list: &[DATA],
+                key: [2; 16],
+            },
+        ];
+        let levels = [SecurityLevelDescriptor {
+            frame_type: frame_type::DATA,
+            command_frame_id: 0,
+            security_minimum: security_levels::ENC_MIC_32,
+            device_override_security_minimum: false,
+        }];
+        let pib = SecurityPib {
+            key_table: &keys,
+            device_table: &DEVICES,
+            security_level_table: &levels,
+            default_key_source: 0xff,
+            pan_id: 0x4321,
+            pan_coord_short_address: 0x0000,
+            pan_coord_extended_address: 0xacde_4800_0000_0000,
+            extended_address: 0xacde_4800_0000_0000,
+        };
+
+        let extended = header(Address::Extended(0xacde_4800_0000_0002));
+        let resolved = pib
+            .resolve_incoming(&extended, &aux(5, KeyIdentifier::None), None)
+            .unwrap();
+        assert_eq!(resolved.key_descriptor.key, [1; 16]);
+        assert_eq!(resolved.device_handle, 1);
+
+        let short = header(Address::Short(0x0002));
+        let resolved = pib
+            .resolve_incoming(&short, &aux(5, KeyIdentifier::Only { index: 3 }), None)
+            .unwrap();
+        assert_eq!(resolved.key_descriptor.key, [2; 16]);
+
+        assert_eq!(
+            pib.resolve_incoming(&short, &aux(5, KeyIdentifier::None), None),
+            Err(SecurityError::UnavailableKey)
+        );
+        let blacklisted = header(Address::Short(0x0001));
+        assert_eq!(
+            pib.resolve_incoming(
+                &blacklisted,
+                &aux(5, KeyIdentifier::Only { index: 3 }),
+                None
+            ),
+            Err(SecurityError::UnavailableKey)
+        );
+        assert_eq!(
+            pib.resolve_incoming(&short, &aux(2, KeyIdentifier::Only { index: 3 }), None),
+            Err(SecurityError::ImproperSecurityLevel)
+        );
+
+        let mut command = short;
+        command
+            .frame_control
+            .set_frame_type(frame_type::MAC_COMMAND);
+        assert_eq!(
+            pib.resolve_incoming(&command, &aux(5, KeyIdentifier::Only { index: 3 }), Some(4)),
+            Err(SecurityError::ImproperKeyType)
+        );
+    }
+
+    #[test]
+    fn unique_device() {
+        let key_devices = [
+            KeyDeviceDescriptor {
+                device_handle: 0,
+                unique_device: true,
+                blacklisted: false,
+            },
+            KeyDeviceDescriptor {
+                device_handle: 1,
+                unique_device: true,
+                blacklisted: true,
+            },
+        ];
+        let keys = [
+            KeyDescriptor {
+                key_id_lookup_list: &[KeyIdLookup::from_key_source_8(0xff, 1)],
+                key_device_list: &key_devices[..1],
+                key_usage_list: &[DATA],
+                key: [1; 16],
+            },
+            KeyDescriptor {
+                key_id_lookup_list: &[KeyIdLookup::from_key_source_8(0xff, 2)],
+                key_device_list: &key_devices[1..],
+                key_usage_list: &[DATA],
+                key: [2; 16],
+            },
+        ];
+        let pib = SecurityPib {
+            key_table: &keys,
+            device_table: &DEVICES,
+            security_level_table: &[],
+            default_key_source: 0xff,
+            pan_id: 0x4321,
+            pan_coord_short_address: 0x0000,
+            pan_coord_extended_address: 0xacde_4800_0000_0000,
+            extended_address: 0xacde_4800_0000_0000,
+        };
+
+        // the originator is identified by the key, whatever its address
+        let unknown = header(Address::Short(0x0003));
+        let resolved = pib
+            .resolve_incoming(&unknown, &aux(5, KeyIdentifier::Only { index: 1 }), None)
+            .unwrap();
+        assert_eq!(resolved.device_handle, 0);
+
+        assert_eq!(
+            pib.resolve_incoming(&unknown, &aux(5, KeyIdentifier::Only { index: 2 }), None),
+            Err(SecurityError::UnavailableKey)
+        );
+    }
+
+    #[test]
+    fn exempt_device() {
+        let devices = [
+            DEVICES[0],
+            DeviceDescriptor {
+                exempt: true,
+                ..DEVICES[1]
+            },
+        ];
+        let levels = [
+            SecurityLevelDescriptor {
+                frame_type: frame_type::DATA,
+                command_frame_id: 0,
+                security_minimum: security_levels::ENC_MIC_32,
+                device_override_security_minimum: true,
+            },
+            SecurityLevelDescriptor {
+                frame_type: frame_type::MAC_COMMAND,
+                command_frame_id: 4,
+                security_minimum: security_levels::ENC_MIC_32,
+                device_override_security_minimum: false,
+            },
+        ];
+        let pib = SecurityPib {
+            key_table: &[],
+            device_table: &devices,
+            security_level_table: &levels,
+            default_key_source: 0xff,
+            pan_id: 0x4321,
+            pan_coord_short_address: 0x0000,
+            pan_coord_extended_address: 0xacde_4800_0000_0000,
+            extended_address: 0xacde_4800_0000_0000,
+        };
+
+        let exempt = header(Address::Short(0x0002));
+        assert_eq!(pib.check_unsecured(&exempt, None), Ok(()));
+        assert_eq!(
+            pib.check_unsecured(&header(Address::Short(0x0001)), None),
+            Err(SecurityError::ImproperSecurityLevel)
+        );
+
+        // the exemption only applies where the security level table allows it
+        let mut command = exempt;
+        command
+            .frame_control
+            .set_frame_type(frame_type::MAC_COMMAND);
+        assert_eq!(
+            pib.check_unsecured(&command, Some(4)),
+            Err(SecurityError::ImproperSecurityLevel)
+        );
+        assert_eq!(pib.check_unsecured(&command, Some(7)), Ok(()));
+    }
+}
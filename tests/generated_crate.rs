@@ -0,0 +1,51 @@
+//! Generates the crate and checks that it builds, passes its tests and is free of clippy
+//! warnings, both as a whole and for each group on its own.
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+use rust_ieee802154_gen::{generate, Config, TARGETS};
+
+fn cargo(dir: &Path, args: &[&str]) {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args(args)
+        .current_dir(dir)
+        // share the build of the generated crates between the runs
+        .env(
+            "CARGO_TARGET_DIR",
+            Path::new(env!("CARGO_TARGET_TMPDIR")).join("target"),
+        )
+        .status()
+        .unwrap();
+
+    assert!(
+        status.success(),
+        "cargo {} failed in {}",
+        args.join(" "),
+        dir.display()
+    );
+}
+
+#[test]
+fn full_crate() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ieee802154");
+    generate(&Config::new(&dir)).unwrap();
+
+    cargo(&dir, &["test"]);
+    cargo(&dir, &["clippy", "--all-targets", "--", "-D", "warnings"]);
+}
+
+#[test]
+fn groups() {
+    let mut groups: Vec<&str> = TARGETS.iter().map(|t| t.group).collect();
+    groups.dedup();
+
+    for group in groups {
+        let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("ieee802154-{}", group));
+        generate(&Config::new(&dir).only(group)).unwrap();
+
+        cargo(&dir, &["test"]);
+    }
+}